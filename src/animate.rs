@@ -0,0 +1,191 @@
+use crate::automaton::Automaton;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Moves the cursor to the top left corner.
+const HOME: &str = "\x1b[H";
+/// Clears everything from the cursor to the end of the screen.
+const CLEAR_BELOW: &str = "\x1b[J";
+/// Clears the entire screen.
+const CLEAR: &str = "\x1b[2J";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+const CONTROLS: &str = "Enter: pause/resume, s + Enter: step, q + Enter: quit";
+
+/// Settings for animating one of the grid simulations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// The name of the day to animate.
+    pub day: String,
+    pub part_b: bool,
+    /// The number of generations drawn every second.
+    pub fps: f64,
+    /// Stops after this many generations, if set.
+    pub cycles: Option<usize>,
+    /// The position in every axis beyond `x` and `y` to draw.
+    pub slice: Option<Vec<i32>>,
+    /// Uses the puzzle input instead of the built-in example.
+    pub input: bool,
+}
+
+impl Options {
+    /// Parses the arguments following `--animate`.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let day = args.next().ok_or("Missing the day to animate.")?.clone();
+        let mut options = Self {
+            day,
+            part_b: false,
+            fps: 4.0,
+            cycles: None,
+            slice: None,
+            input: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing a value for {}.", arg));
+            match arg.as_str() {
+                "--part" => {
+                    options.part_b = match value()?.as_str() {
+                        "a" => false,
+                        "b" => true,
+                        part => return Err(format!("Unknown part: {}", part)),
+                    }
+                }
+                "--fps" => {
+                    let fps = value()?;
+                    options.fps = fps
+                        .parse()
+                        .ok()
+                        .filter(|&f: &f64| f > 0.0)
+                        .ok_or(format!("Invalid frame rate: {}", fps))?;
+                }
+                "--cycles" => {
+                    let cycles = value()?;
+                    let cycles = cycles
+                        .parse()
+                        .map_err(|_| format!("Invalid cycles: {}", cycles))?;
+                    options.cycles = Some(cycles);
+                }
+                "--slice" => {
+                    let slice = value()?;
+                    let slice = slice
+                        .split(',')
+                        .map(|s| s.trim().parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| format!("Invalid slice: {}", slice))?;
+                    options.slice = Some(slice);
+                }
+                "--input" => options.input = true,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A key pressed by the user, followed by Enter.
+enum Command {
+    Toggle,
+    Step,
+    Quit,
+}
+
+/// Reads commands from stdin on a separate thread so the animation can
+/// keep going in the meantime.
+fn controls() -> Receiver<Command> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let command = match line.as_ref().map(|l| l.trim()) {
+                Ok("s") => Command::Step,
+                Ok("q") | Err(_) => Command::Quit,
+                Ok(_) => Command::Toggle,
+            };
+            if tx.send(command).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Draws every generation in place until one repeats an earlier one in
+/// the same position, or until the number of cycles runs out.
+pub fn play<T, F>(first: T, render: F, options: &Options)
+where
+    T: Automaton,
+    F: Fn(&T) -> String,
+{
+    let frame = Duration::from_secs_f64(1.0 / options.fps);
+    let commands = controls();
+    let mut seen = HashMap::new();
+    let mut current = first;
+    let mut generation = 0;
+    let mut paused = false;
+    print!("{}{}", CLEAR, HIDE_CURSOR);
+    loop {
+        let status = if paused { "paused" } else { "running" };
+        print!("{}{}{}", HOME, render(&current), CLEAR_BELOW);
+        println!("\nGeneration {} ({})\n{}", generation, status, CONTROLS);
+        io::stdout().flush().unwrap();
+
+        // The same generation is drawn again after pausing or resuming.
+        let key = current.key();
+        if let Some(earlier) = seen.get(&key).filter(|&&g| g != generation) {
+            println!("Generation {} repeats generation {}.", generation, earlier);
+            break;
+        }
+        seen.insert(key, generation);
+        if options.cycles == Some(generation) {
+            break;
+        }
+        let command = if paused {
+            commands.recv().ok()
+        } else {
+            match commands.recv_timeout(frame) {
+                Ok(command) => Some(command),
+                Err(RecvTimeoutError::Timeout) => None,
+                // There's no way to pause anymore, e.g. if stdin was closed.
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(frame);
+                    None
+                }
+            }
+        };
+        match command {
+            Some(Command::Toggle) => {
+                paused = !paused;
+                continue;
+            }
+            Some(Command::Step) => paused = true,
+            Some(Command::Quit) => break,
+            None if paused => paused = false,
+            None => {}
+        }
+        current.step();
+        generation += 1;
+    }
+    print!("{}", SHOW_CURSOR);
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn test_options() {
+    let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<_>>();
+    let options = Options::parse(&args("conway --part b --fps 10 --slice 1,-2")).unwrap();
+    assert!(options.part_b);
+    assert_eq!(options.fps, 10.0);
+    assert_eq!(options.slice, Some(vec![1, -2]));
+    assert!(!options.input);
+
+    let options = Options::parse(&args("ferry --input --cycles 3")).unwrap();
+    assert_eq!((options.day.as_str(), options.cycles), ("ferry", Some(3)));
+    assert!(Options::parse(&args("ferry --fps 0")).is_err());
+    assert!(Options::parse(&args("ferry --part c")).is_err());
+    assert!(Options::parse(&args("ferry --slice")).is_err());
+    assert!(Options::parse(&[]).is_err());
+}
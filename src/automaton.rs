@@ -0,0 +1,439 @@
+use crate::grid::{Grid, ADJACENT};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// Anything which changes one generation at a time, such as a grid of
+/// seats or a space full of cubes.
+pub trait Automaton: Sized {
+    /// The shape of a generation, ignoring where it is.
+    type Key: Hash + Eq;
+
+    /// Replaces this generation with the next one.
+    fn step(&mut self);
+
+    /// The shape of this generation along with its position. Generations
+    /// which only differ by a translation must share the same key. Fixed
+    /// grids which can't move may return an empty position.
+    fn key(&self) -> (Self::Key, Vec<i32>);
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Nothing changes anymore. This includes patterns which died out.
+    Stable,
+    /// The pattern repeats in place after `period` generations.
+    Oscillator { period: usize },
+    /// The pattern repeats after `period` generations, but it has moved
+    /// by `displacement`.
+    Spaceship {
+        period: usize,
+        displacement: Vec<i32>,
+    },
+    /// The pattern didn't repeat before the limit was reached.
+    Unresolved,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Outcome::Stable => write!(f, "stable"),
+            Outcome::Oscillator { period } => write!(f, "oscillator (period {})", period),
+            Outcome::Spaceship {
+                period,
+                displacement,
+            } => write!(
+                f,
+                "spaceship (period {}, moving {:?})",
+                period, displacement
+            ),
+            Outcome::Unresolved => write!(f, "unresolved"),
+        }
+    }
+}
+
+/// The result of running an automaton until it repeats.
+#[derive(Debug, Clone)]
+pub struct Summary<T> {
+    pub outcome: Outcome,
+    /// The first generation of the cycle, if there is one.
+    pub start: Option<usize>,
+    /// The number of steps taken. For any cycle, this is the generation
+    /// where it first repeated.
+    pub steps: usize,
+    /// The final generation.
+    pub last: T,
+}
+
+/// Steps through generations until one of them repeats an earlier one,
+/// or until `limit` steps have been taken. Only a hash of each generation
+/// is kept, so a matching hash is confirmed by replaying the earlier
+/// generation from the first one.
+pub fn run<T: Automaton + Clone>(first: T, limit: usize) -> Summary<T> {
+    let mut seen: HashMap<u64, Vec<(usize, Vec<i32>)>> = HashMap::new();
+    let mut current = first.clone();
+    let mut steps = 0;
+    loop {
+        let (key, position) = current.key();
+        let hash = hash_of(&key);
+        let mut candidates = seen.get(&hash).into_iter().flatten();
+        let repeat = candidates.find(|(start, _)| replay(&first, *start).key().0 == key);
+        if let Some((start, earlier)) = repeat {
+            let period = steps - start;
+            let displacement: Vec<i32> = position.iter().zip(earlier).map(|(p, e)| p - e).collect();
+            let outcome = if displacement.iter().any(|&d| d != 0) {
+                Outcome::Spaceship {
+                    period,
+                    displacement,
+                }
+            } else if period == 1 {
+                Outcome::Stable
+            } else {
+                Outcome::Oscillator { period }
+            };
+            return Summary {
+                outcome,
+                start: Some(*start),
+                steps,
+                last: current,
+            };
+        }
+        if steps == limit {
+            return Summary {
+                outcome: Outcome::Unresolved,
+                start: None,
+                steps,
+                last: current,
+            };
+        }
+        seen.entry(hash).or_default().push((steps, position));
+        current.step();
+        steps += 1;
+    }
+}
+
+fn hash_of<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Steps a copy of the first generation up to the given one.
+fn replay<T: Automaton + Clone>(first: &T, generation: usize) -> T {
+    let mut current = first.clone();
+    for _ in 0..generation {
+        current.step();
+    }
+    current
+}
+
+/// Decides which cells count as the neighbors of any given cell.
+#[derive(Clone)]
+pub enum Neighborhood<T> {
+    /// The eight cells touching this one, including diagonals.
+    Adjacent,
+    /// The four cells sharing an edge with this one.
+    Orthogonal,
+    /// The first cell seen in each of the eight directions, skipping any
+    /// cells which can be seen through. Neighbors are only found once, so
+    /// cells which can be seen through must never change.
+    LineOfSight(fn(&T) -> bool),
+    /// The cells at each of these `(x, y)` offsets.
+    Offsets(Vec<(isize, isize)>),
+}
+
+/// Decides the next value of a cell from its current value and the
+/// values of its neighbors.
+pub type Transition<T> = fn(T, &[T]) -> T;
+
+/// A rectangular grid of cells which are all updated at once. Every
+/// cell's neighbors are found once up front, so each step only visits
+/// those. The next generation is written into a second buffer, so
+/// stepping doesn't need to allocate. Copies share the neighbors.
+#[derive(Clone)]
+pub struct GridAutomaton<T> {
+    cells: Grid<T>,
+    buffer: Grid<T>,
+    topology: Rc<Topology>,
+    /// The cells which might change in the next incremental step.
+    pending: Vec<usize>,
+    transition: Transition<T>,
+}
+
+/// How the cells of a grid automaton are connected, which never changes.
+struct Topology {
+    /// The index of every neighbor of every cell, one cell after another.
+    links: Vec<usize>,
+    /// Where the neighbors of each cell start in `links`.
+    starts: Vec<usize>,
+    /// The index of every cell which has each cell as a neighbor.
+    watchers: Vec<Vec<usize>>,
+}
+
+impl<T: Copy + PartialEq> GridAutomaton<T> {
+    pub fn new(grid: &Grid<T>, neighborhood: Neighborhood<T>, transition: Transition<T>) -> Self {
+        let mut topology = Topology {
+            links: Vec::new(),
+            starts: vec![0],
+            watchers: vec![Vec::new(); grid.len()],
+        };
+        for i in 0..grid.len() {
+            for j in Self::link(grid, i, &neighborhood) {
+                topology.links.push(j);
+                topology.watchers[j].push(i);
+            }
+            topology.starts.push(topology.links.len());
+        }
+        Self {
+            cells: grid.clone(),
+            buffer: grid.clone(),
+            topology: Rc::new(topology),
+            pending: (0..grid.len()).collect(),
+            transition,
+        }
+    }
+
+    /// The current generation.
+    pub fn grid(&self) -> &Grid<T> {
+        &self.cells
+    }
+
+    /// Counts the cells matching the predicate.
+    pub fn count<F: Fn(&T) -> bool>(&self, f: F) -> usize {
+        self.cells.iter().filter(|c| f(c)).count()
+    }
+
+    /// Writes the values of every neighbor of `(x, y)` into `out`.
+    pub fn neighbors(&self, x: usize, y: usize, out: &mut Vec<T>) {
+        let i = self.cells.index_of(x, y).expect("Index out of bounds.");
+        self.gather(i, out);
+    }
+
+    /// Updates every cell once, returning the number of cells changed.
+    pub fn step_in_place(&mut self) -> usize {
+        let mut changed = Vec::new();
+        let mut neighbors = Vec::new();
+        for i in 0..self.cells.len() {
+            self.gather(i, &mut neighbors);
+            let current = self.cells.as_slice()[i];
+            let next = (self.transition)(current, &neighbors);
+            self.buffer.as_mut_slice()[i] = next;
+            if next != current {
+                changed.push(i);
+            }
+        }
+        std::mem::swap(&mut self.cells, &mut self.buffer);
+        self.watch(&changed);
+        changed.len()
+    }
+
+    /// Updates only the cells which changed or had a neighbor change in
+    /// the previous step. Every other cell would stay the same anyway.
+    /// Returns the number of cells changed.
+    pub fn step_incremental(&mut self) -> usize {
+        let mut changes = Vec::new();
+        let mut neighbors = Vec::new();
+        let cells = self.cells.as_slice();
+        for &i in &self.pending {
+            self.gather(i, &mut neighbors);
+            let next = (self.transition)(cells[i], &neighbors);
+            if next != cells[i] {
+                changes.push((i, next));
+            }
+        }
+        let cells = self.cells.as_mut_slice();
+        for &(i, next) in &changes {
+            cells[i] = next;
+        }
+        let changed: Vec<usize> = changes.into_iter().map(|(i, _)| i).collect();
+        self.watch(&changed);
+        changed.len()
+    }
+
+    /// Steps until nothing changes, returning the number of steps which
+    /// changed anything. This never ends for grids which oscillate.
+    pub fn settle(&mut self) -> usize {
+        let mut steps = 0;
+        while self.step_incremental() > 0 {
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Writes the values of every neighbor of cell `i` into `out`.
+    fn gather(&self, i: usize, out: &mut Vec<T>) {
+        out.clear();
+        let Topology { links, starts, .. } = &*self.topology;
+        let links = &links[starts[i]..starts[i + 1]];
+        out.extend(links.iter().map(|&j| self.cells.as_slice()[j]));
+    }
+
+    /// Queues every cell which could be affected by these changes.
+    fn watch(&mut self, changed: &[usize]) {
+        self.pending.clear();
+        for &i in changed {
+            self.pending.push(i);
+            self.pending.extend(&self.topology.watchers[i]);
+        }
+        self.pending.sort_unstable();
+        self.pending.dedup();
+    }
+
+    /// Finds the index of every neighbor of cell `i`.
+    fn link(grid: &Grid<T>, i: usize, neighborhood: &Neighborhood<T>) -> Vec<usize> {
+        let (x, y) = (i % grid.width(), i / grid.width());
+        let positions: Vec<(usize, usize)> = match neighborhood {
+            Neighborhood::Adjacent => grid.neighbors8(x, y).collect(),
+            Neighborhood::Orthogonal => grid.neighbors4(x, y).collect(),
+            Neighborhood::Offsets(offsets) => offsets
+                .iter()
+                .filter_map(|&(dx, dy)| grid.offset(x, y, dx, dy))
+                .collect(),
+            Neighborhood::LineOfSight(transparent) => ADJACENT
+                .iter()
+                .filter_map(|&(dx, dy)| grid.ray(x, y, dx, dy).find(|&p| !transparent(&grid[p])))
+                .collect(),
+        };
+        let index = |(x, y)| grid.index_of(x, y).unwrap();
+        positions.into_iter().map(index).collect()
+    }
+}
+
+/// Grids never move, so they don't have a position.
+impl<T: Copy + Hash + Eq> Automaton for GridAutomaton<T> {
+    type Key = Grid<T>;
+
+    fn step(&mut self) {
+        self.step_in_place();
+    }
+
+    fn key(&self) -> (Grid<T>, Vec<i32>) {
+        (self.cells.clone(), Vec::new())
+    }
+}
+
+/// A point moving around a ring, which is useful for testing.
+#[cfg(test)]
+#[derive(Clone)]
+struct Ring {
+    position: i32,
+    size: i32,
+    speed: i32,
+    /// Where the point stops moving.
+    stop: Option<i32>,
+}
+
+#[cfg(test)]
+impl Automaton for Ring {
+    type Key = i32;
+
+    fn step(&mut self) {
+        if self.stop != Some(self.position) {
+            self.position = (self.position + self.speed) % self.size;
+        }
+    }
+
+    fn key(&self) -> (i32, Vec<i32>) {
+        (self.position, Vec::new())
+    }
+}
+
+#[test]
+fn test_run() {
+    let ring = |speed, stop| Ring {
+        position: 0,
+        size: 6,
+        speed,
+        stop,
+    };
+    let summary = run(ring(2, None), 100);
+    assert_eq!(summary.outcome, Outcome::Oscillator { period: 3 });
+    assert_eq!((summary.start, summary.steps), (Some(0), 3));
+
+    let summary = run(ring(1, Some(4)), 100);
+    assert_eq!(summary.outcome, Outcome::Stable);
+    assert_eq!((summary.start, summary.steps), (Some(4), 5));
+    assert_eq!(summary.last.position, 4);
+
+    let summary = run(ring(1, None), 3);
+    assert_eq!(summary.outcome, Outcome::Unresolved);
+    assert_eq!((summary.start, summary.steps), (None, 3));
+
+    // Every generation shares a hash, so only replaying tells them apart.
+    let summary = run(Colliding(ring(2, None)), 100);
+    assert_eq!(summary.outcome, Outcome::Oscillator { period: 3 });
+    assert_eq!((summary.start, summary.steps), (Some(0), 3));
+}
+
+/// A ring whose keys all have the same hash.
+#[cfg(test)]
+#[derive(Clone)]
+struct Colliding(Ring);
+
+#[cfg(test)]
+#[derive(PartialEq, Eq)]
+struct SameHash(i32);
+
+#[cfg(test)]
+impl Hash for SameHash {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+#[cfg(test)]
+impl Automaton for Colliding {
+    type Key = SameHash;
+
+    fn step(&mut self) {
+        self.0.step();
+    }
+
+    fn key(&self) -> (SameHash, Vec<i32>) {
+        (SameHash(self.0.position), Vec::new())
+    }
+}
+
+#[test]
+fn test_grid() {
+    // Cells with exactly one neighbor turn on. Everything else turns off.
+    let grid = Grid::new(5, 5, false);
+    let mut grid = GridAutomaton::new(&grid, Neighborhood::Adjacent, |_, n| {
+        n.iter().filter(|&&b| b).count() == 1
+    });
+    grid.cells[(2, 2)] = true;
+    assert_eq!(grid.step_in_place(), 9);
+    assert_eq!(grid.count(|&b| b), 8);
+    assert_eq!(grid.grid().row(1), &[false, true, true, true, false]);
+
+    let mut out = Vec::new();
+    let wide = Grid::parse("1002\n0000\n0030", |c| c.to_digit(10).unwrap());
+    let sight = GridAutomaton::new(&wide, Neighborhood::LineOfSight(|&c| c == 0), |c, _| c);
+    sight.neighbors(0, 0, &mut out);
+    assert_eq!(out, vec![2, 3]);
+    let knight = Neighborhood::Offsets(vec![(1, 2), (2, 1), (-1, 2)]);
+    GridAutomaton::new(&wide, knight, |c, _| c).neighbors(1, 0, &mut out);
+    assert_eq!(out, vec![3, 0, 0]);
+    GridAutomaton::new(&wide, Neighborhood::Orthogonal, |c, _| c).neighbors(3, 0, &mut out);
+    assert_eq!(out, vec![0, 0]);
+}
+
+#[test]
+fn test_incremental() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(40);
+    let grid = Grid::from_fn(30, 30, |_, _| rng.gen_bool(0.4));
+    let life = |alive, n: &[bool]| match n.iter().filter(|&&b| b).count() {
+        3 => true,
+        2 => alive,
+        _ => false,
+    };
+    let mut full = GridAutomaton::new(&grid, Neighborhood::Adjacent, life);
+    let mut incremental = full.clone();
+    for _ in 0..50 {
+        assert_eq!(full.step_in_place(), incremental.step_incremental());
+        assert_eq!(full.cells, incremental.cells);
+    }
+    assert!(incremental.pending.len() < grid.len());
+}
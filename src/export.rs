@@ -0,0 +1,226 @@
+use crate::grid::Grid;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The color used to draw lines between tiles.
+const TILE_COLOR: [u8; 3] = [128, 128, 128];
+/// The color used to draw highlighted cells.
+const HIGHLIGHT_COLOR: [u8; 3] = [220, 40, 40];
+
+/// Anything which can be drawn as a single cell in an exported image.
+pub trait Pixel {
+    /// The color of this cell as `[r, g, b]`.
+    fn color(&self) -> [u8; 3];
+}
+
+/// Active cells are black. Everything else is white.
+impl Pixel for bool {
+    fn color(&self) -> [u8; 3] {
+        if *self {
+            [0, 0, 0]
+        } else {
+            [255, 255, 255]
+        }
+    }
+}
+
+/// A grid of colors which can be written as a netpbm or SVG file.
+#[derive(Debug, Clone)]
+pub struct Picture {
+    colors: Grid<[u8; 3]>,
+    /// Draws a line before every `tile` cells in both directions, if set.
+    tile: Option<usize>,
+    /// Cells drawn in the highlight color as `(x, y)`.
+    highlights: HashSet<(usize, usize)>,
+    /// The number of pixels used for each cell in the netpbm formats.
+    scale: usize,
+}
+
+impl Picture {
+    /// Constructs a new picture with one cell for every value in the grid.
+    pub fn new<T: Pixel>(grid: &Grid<T>) -> Self {
+        Self {
+            colors: grid.map(Pixel::color),
+            tile: None,
+            highlights: HashSet::new(),
+            scale: 1,
+        }
+    }
+
+    /// Draws the boundaries between tiles of `size` x `size` cells. In the
+    /// netpbm formats, they are only visible when the scale is at least 2.
+    pub fn with_tiles(mut self, size: usize) -> Self {
+        self.tile = Some(size).filter(|&s| s > 0);
+        self
+    }
+
+    /// Draws each of these cells in the highlight color.
+    pub fn with_highlights<I: IntoIterator<Item = (usize, usize)>>(mut self, cells: I) -> Self {
+        self.highlights.extend(cells);
+        self
+    }
+
+    /// Draws every cell as a `scale` x `scale` square in the netpbm formats.
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Writes a black and white image, where any dark color is black.
+    pub fn to_pbm(&self) -> String {
+        self.to_netpbm("P1", None, |c| {
+            let black = luminance(c) < 128;
+            (black as u8).to_string()
+        })
+    }
+
+    /// Writes a grayscale image.
+    pub fn to_pgm(&self) -> String {
+        self.to_netpbm("P2", Some(255), |c| luminance(c).to_string())
+    }
+
+    /// Writes a full color image.
+    pub fn to_ppm(&self) -> String {
+        self.to_netpbm("P3", Some(255), |[r, g, b]| format!("{} {} {}", r, g, b))
+    }
+
+    /// Writes a vector image. Each cell is a 1 x 1 square.
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        let (w, h) = (self.colors.width(), self.colors.height());
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            w, h
+        )
+        .unwrap();
+        writeln!(svg, r#"<rect width="{}" height="{}" fill="white"/>"#, w, h).unwrap();
+        for y in 0..h {
+            for x in 0..w {
+                let color = self.cell(x, y);
+                if color != [255, 255, 255] {
+                    writeln!(
+                        svg,
+                        r#"<rect x="{}" y="{}" width="1" height="1" fill="{}"/>"#,
+                        x,
+                        y,
+                        hex(color)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        if let Some(tile) = self.tile {
+            let stroke = format!(r#"stroke="{}" stroke-width="0.1""#, hex(TILE_COLOR));
+            for x in (0..=w).step_by(tile) {
+                writeln!(
+                    svg,
+                    r#"<line x1="{0}" y1="0" x2="{0}" y2="{1}" {2}/>"#,
+                    x, h, stroke
+                )
+                .unwrap();
+            }
+            for y in (0..=h).step_by(tile) {
+                writeln!(
+                    svg,
+                    r#"<line x1="0" y1="{0}" x2="{1}" y2="{0}" {2}/>"#,
+                    y, w, stroke
+                )
+                .unwrap();
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Writes the picture to a file, choosing the format by its extension:
+    /// `pbm`, `pgm`, `ppm` or `svg`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("pbm") => self.to_pbm(),
+            Some("pgm") => self.to_pgm(),
+            Some("ppm") => self.to_ppm(),
+            Some("svg") => self.to_svg(),
+            _ => {
+                let msg = format!("Unknown image format: {}", path.display());
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+            }
+        };
+        fs::write(path, contents)
+    }
+
+    /// The color of a cell, including any highlights.
+    fn cell(&self, x: usize, y: usize) -> [u8; 3] {
+        if self.highlights.contains(&(x, y)) {
+            HIGHLIGHT_COLOR
+        } else {
+            self.colors[(x, y)]
+        }
+    }
+
+    /// The color of a single pixel after scaling, including tile lines.
+    fn pixel(&self, px: usize, py: usize) -> [u8; 3] {
+        let (x, y) = (px / self.scale, py / self.scale);
+        if let Some(tile) = self.tile.filter(|_| self.scale > 1) {
+            let left = px.is_multiple_of(self.scale) && x.is_multiple_of(tile);
+            let top = py.is_multiple_of(self.scale) && y.is_multiple_of(tile);
+            if left || top {
+                return TILE_COLOR;
+            }
+        }
+        self.cell(x, y)
+    }
+
+    fn to_netpbm<F: Fn([u8; 3]) -> String>(&self, magic: &str, max: Option<u8>, f: F) -> String {
+        let (w, h) = (self.colors.width(), self.colors.height());
+        let (w, h) = (w * self.scale, h * self.scale);
+        let mut out = format!("{}\n{} {}\n", magic, w, h);
+        if let Some(max) = max {
+            writeln!(out, "{}", max).unwrap();
+        }
+        for py in 0..h {
+            let line: Vec<String> = (0..w).map(|px| f(self.pixel(px, py))).collect();
+            writeln!(out, "{}", line.join(" ")).unwrap();
+        }
+        out
+    }
+}
+
+/// The perceived brightness of a color from 0 to 255.
+fn luminance([r, g, b]: [u8; 3]) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[test]
+fn test_netpbm() {
+    let grid = Grid::from_rows(vec![vec![true, false], vec![false, true]]).unwrap();
+    let picture = Picture::new(&grid);
+    assert_eq!(picture.to_pbm(), "P1\n2 2\n1 0\n0 1\n");
+    assert_eq!(picture.to_pgm(), "P2\n2 2\n255\n0 255\n255 0\n");
+    let picture = picture
+        .with_highlights(vec![(1, 1)])
+        .with_tiles(1)
+        .with_scale(2);
+    let ppm = picture.to_ppm();
+    let lines: Vec<&str> = ppm.lines().collect();
+    assert_eq!(lines[1], "4 4");
+    assert_eq!(lines[3], "128 128 128 128 128 128 128 128 128 128 128 128");
+    assert_eq!(lines[6], "128 128 128 255 255 255 128 128 128 220 40 40");
+}
+
+#[test]
+fn test_svg() {
+    let grid = Grid::from_rows(vec![vec![true, false, false]]).unwrap();
+    let svg = Picture::new(&grid).with_tiles(3).to_svg();
+    assert!(svg.contains(r##"<rect x="0" y="0" width="1" height="1" fill="#000000"/>"##));
+    assert!(!svg.contains(r#"<rect x="1""#));
+    assert_eq!(svg.matches("<line").count(), 3);
+}
@@ -0,0 +1,857 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A single element in one of the alternatives of a rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    /// A reference to another rule by its id.
+    Ref(usize),
+    /// A literal sequence of characters. Must not be empty.
+    Literal(String),
+}
+
+impl Symbol {
+    /// Writes this symbol as it would appear in EBNF.
+    fn to_ebnf(&self) -> String {
+        match self {
+            Symbol::Ref(id) => format!("r{}", id),
+            Symbol::Literal(lit) if lit.contains('"') => format!("'{}'", lit),
+            Symbol::Literal(lit) => format!("\"{}\"", lit),
+        }
+    }
+
+    /// Retrieves the id of the rule being referenced, if this is a reference.
+    fn rule_id(&self) -> Option<usize> {
+        match self {
+            Symbol::Ref(id) => Some(*id),
+            Symbol::Literal(_) => None,
+        }
+    }
+}
+
+/// A set of numbered rules, where each rule is any number of alternative
+/// sequences of symbols. Unlike a regular expression, rules are free to
+/// refer to themselves, either directly or through any other rules.
+#[derive(Debug, Clone, Default)]
+pub struct Grammar {
+    rules: BTreeMap<usize, Vec<Vec<Symbol>>>,
+}
+
+impl Grammar {
+    /// Constructs a new grammar with no rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines or replaces the alternatives of a rule, yielding the old ones.
+    pub fn set_rule(&mut self, id: usize, alts: Vec<Vec<Symbol>>) -> Option<Vec<Vec<Symbol>>> {
+        self.rules.insert(id, alts)
+    }
+
+    /// Retrieves the alternatives of a rule, if it is defined.
+    pub fn get_rule(&self, id: usize) -> Option<&[Vec<Symbol>]> {
+        self.rules.get(&id).map(Vec::as_slice)
+    }
+
+    /// Finds every reference to an undefined rule as `(rule, reference)`.
+    pub fn undefined_refs(&self) -> Vec<(usize, usize)> {
+        let mut undefined = Vec::new();
+        for (&id, alts) in &self.rules {
+            for r in alts.iter().flatten().filter_map(Symbol::rule_id) {
+                if !self.rules.contains_key(&r) && !undefined.contains(&(id, r)) {
+                    undefined.push((id, r));
+                }
+            }
+        }
+        undefined
+    }
+
+    /// Finds every defined rule which can never be used when deriving
+    /// messages from `start`.
+    pub fn unreachable(&self, start: usize) -> Vec<usize> {
+        let mut reached = HashSet::new();
+        let mut queue = vec![start];
+        while let Some(id) = queue.pop() {
+            if !reached.insert(id) {
+                continue;
+            }
+            if let Some(alts) = self.rules.get(&id) {
+                queue.extend(alts.iter().flatten().filter_map(Symbol::rule_id));
+            }
+        }
+        self.rules
+            .keys()
+            .copied()
+            .filter(|id| !reached.contains(id))
+            .collect()
+    }
+
+    /// Determines whether the entire message can be derived from `rule`.
+    pub fn is_match(&self, rule: usize, message: &str) -> bool {
+        Chart::parse(self, rule, message).is_complete()
+    }
+
+    /// Generates every possible derivation of the entire message from `rule`.
+    /// The output is empty when the message does not match, and contains more
+    /// than one element when the grammar is ambiguous for this message.
+    /// Derivations which loop back to the same rule and span are skipped, as
+    /// there would otherwise be infinitely many of them.
+    pub fn derivations(&self, rule: usize, message: &str) -> Vec<Derivation> {
+        let chart = Chart::parse(self, rule, message);
+        let builder = TreeBuilder::new(self, &chart, message);
+        builder.build(rule, 0, chart.len, &mut Vec::new())
+    }
+
+    /// Finds the length of the longest prefix of the message which can be
+    /// derived from `rule`. This is mostly useful for figuring out where
+    /// a message stops matching.
+    pub fn longest_match(&self, rule: usize, message: &str) -> Option<usize> {
+        let chart = Chart::parse(self, rule, message);
+        (0..=chart.len)
+            .rev()
+            .find(|&end| chart.completed.contains(&(rule, 0, end)))
+    }
+
+    /// Retrieves the symbol directly after the dot in an item, if any.
+    fn next_symbol(&self, item: &Item) -> Option<&Symbol> {
+        self.rules[&item.rule][item.alt].get(item.dot)
+    }
+}
+
+/// A partially matched alternative of a rule. The dot marks how many
+/// symbols have been matched so far, starting at `origin`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Item {
+    rule: usize,
+    alt: usize,
+    dot: usize,
+    origin: usize,
+}
+
+impl Item {
+    fn advance(self) -> Self {
+        Self {
+            dot: self.dot + 1,
+            ..self
+        }
+    }
+}
+
+/// The output of an Earley recognizer over a single message. Every symbol
+/// consumes at least one character, so left recursion, right recursion and
+/// ambiguity are all handled without any special treatment.
+struct Chart {
+    /// The rule the message is expected to match.
+    start: usize,
+    /// The length of the message in bytes.
+    len: usize,
+    /// Every span `(rule, from, to)` that a rule was able to match.
+    completed: HashSet<(usize, usize, usize)>,
+}
+
+impl Chart {
+    fn parse(grammar: &Grammar, start: usize, message: &str) -> Self {
+        let bytes = message.as_bytes();
+        let len = bytes.len();
+        let mut sets: Vec<Vec<Item>> = vec![Vec::new(); len + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); len + 1];
+        let mut completed = HashSet::new();
+
+        Self::predict(grammar, start, 0, &mut sets[0], &mut seen[0]);
+        for k in 0..=len {
+            let mut i = 0;
+            while i < sets[k].len() {
+                let item = sets[k][i];
+                i += 1;
+                match grammar.next_symbol(&item) {
+                    Some(Symbol::Ref(rule)) => {
+                        Self::predict(grammar, *rule, k, &mut sets[k], &mut seen[k]);
+                    }
+                    Some(Symbol::Literal(lit)) => {
+                        let end = k + lit.len();
+                        if !lit.is_empty() && bytes[k..].starts_with(lit.as_bytes()) {
+                            Self::add(item.advance(), &mut sets[end], &mut seen[end]);
+                        }
+                    }
+                    None => {
+                        // Empty alternatives are never predicted, so the origin
+                        // set is always finished by the time we get here.
+                        completed.insert((item.rule, item.origin, k));
+                        let parents: Vec<Item> = sets[item.origin]
+                            .iter()
+                            .filter(|p| grammar.next_symbol(p) == Some(&Symbol::Ref(item.rule)))
+                            .map(|p| p.advance())
+                            .collect();
+                        for parent in parents {
+                            Self::add(parent, &mut sets[k], &mut seen[k]);
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            start,
+            len,
+            completed,
+        }
+    }
+
+    /// Adds every non-empty alternative of `rule` to the set at position `k`.
+    /// Undefined rules simply never match.
+    fn predict(
+        grammar: &Grammar,
+        rule: usize,
+        k: usize,
+        set: &mut Vec<Item>,
+        seen: &mut HashSet<Item>,
+    ) {
+        if let Some(alts) = grammar.get_rule(rule) {
+            for (alt, symbols) in alts.iter().enumerate() {
+                if !symbols.is_empty() {
+                    let item = Item {
+                        rule,
+                        alt,
+                        dot: 0,
+                        origin: k,
+                    };
+                    Self::add(item, set, seen);
+                }
+            }
+        }
+    }
+
+    fn add(item: Item, set: &mut Vec<Item>, seen: &mut HashSet<Item>) {
+        if seen.insert(item) {
+            set.push(item);
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.completed.contains(&(self.start, 0, self.len))
+    }
+}
+
+/// One way in which a rule was able to derive a span of the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Derivation {
+    /// The id of the rule being derived.
+    pub rule: usize,
+    /// The index of the alternative that matched.
+    pub alt: usize,
+    /// The position of the first byte in this span.
+    pub start: usize,
+    /// The position directly after the last byte in this span.
+    pub end: usize,
+    /// One node for each symbol in the alternative.
+    pub children: Vec<Node>,
+}
+
+/// A single matched symbol inside of a [`Derivation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// A nested rule and its derivation.
+    Rule(Derivation),
+    /// A literal and the position where it was found.
+    Literal(usize, String),
+}
+
+impl Derivation {
+    /// Writes this tree as a single JSON object.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+
+    fn write_json(&self, json: &mut String) {
+        json.push_str(&format!(
+            r#"{{"rule":{},"alt":{},"start":{},"end":{},"children":["#,
+            self.rule, self.alt, self.start, self.end
+        ));
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            match child {
+                Node::Rule(d) => d.write_json(json),
+                Node::Literal(start, text) => json.push_str(&format!(
+                    r#"{{"literal":"{}","start":{}}}"#,
+                    escape_json(text),
+                    start
+                )),
+            }
+        }
+        json.push_str("]}");
+    }
+
+    fn write_indented(&self, f: &mut Formatter<'_>, depth: usize) -> FmtResult {
+        let indent = "  ".repeat(depth);
+        writeln!(
+            f,
+            "{}{} (alt {}) @ {}..{}",
+            indent, self.rule, self.alt, self.start, self.end
+        )?;
+        for child in &self.children {
+            match child {
+                Node::Rule(d) => d.write_indented(f, depth + 1)?,
+                Node::Literal(start, text) => writeln!(f, "{}  {:?} @ {}", indent, text, start)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displays the derivation as an indented tree, one symbol per line.
+impl Display for Derivation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.write_indented(f, 0)
+    }
+}
+
+/// Escapes the characters which are not allowed inside of a JSON string.
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reconstructs derivation trees from the spans in a finished chart.
+struct TreeBuilder<'a> {
+    grammar: &'a Grammar,
+    message: &'a str,
+    /// The end of every span matched by a rule, keyed by `(rule, start)`.
+    ends: HashMap<(usize, usize), Vec<usize>>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn new(grammar: &'a Grammar, chart: &Chart, message: &'a str) -> Self {
+        let mut ends: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for &(rule, from, to) in &chart.completed {
+            ends.entry((rule, from)).or_default().push(to);
+        }
+        Self {
+            grammar,
+            message,
+            ends,
+        }
+    }
+
+    /// Generates every derivation of `rule` spanning exactly `from..to`.
+    /// `stack` holds the spans currently being derived to avoid cycles.
+    fn build(
+        &self,
+        rule: usize,
+        from: usize,
+        to: usize,
+        stack: &mut Vec<(usize, usize, usize)>,
+    ) -> Vec<Derivation> {
+        let span = (rule, from, to);
+        if stack.contains(&span) || !self.spans(rule, from).contains(&to) {
+            return Vec::new();
+        }
+        stack.push(span);
+        let mut out = Vec::new();
+        for (alt, symbols) in self.grammar.get_rule(rule).unwrap().iter().enumerate() {
+            for children in self.sequences(symbols, from, to, stack) {
+                out.push(Derivation {
+                    rule,
+                    alt,
+                    start: from,
+                    end: to,
+                    children,
+                });
+            }
+        }
+        stack.pop();
+        out
+    }
+
+    /// Generates every way that `symbols` can be matched over `from..to`.
+    fn sequences(
+        &self,
+        symbols: &[Symbol],
+        from: usize,
+        to: usize,
+        stack: &mut Vec<(usize, usize, usize)>,
+    ) -> Vec<Vec<Node>> {
+        let (first, rest) = match symbols.split_first() {
+            Some(split) => split,
+            None if from == to => return vec![Vec::new()],
+            None => return Vec::new(),
+        };
+        let heads: Vec<(Node, usize)> = match first {
+            Symbol::Literal(lit) => {
+                let end = from + lit.len();
+                if !lit.is_empty() && self.message.as_bytes()[from..].starts_with(lit.as_bytes()) {
+                    vec![(Node::Literal(from, lit.clone()), end)]
+                } else {
+                    Vec::new()
+                }
+            }
+            Symbol::Ref(r) => self
+                .spans(*r, from)
+                .iter()
+                .filter(|&&end| end <= to)
+                .flat_map(|&end| {
+                    self.build(*r, from, end, stack)
+                        .into_iter()
+                        .map(move |d| (Node::Rule(d), end))
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        };
+        let mut out = Vec::new();
+        for (head, end) in heads {
+            for mut tail in self.sequences(rest, end, to, stack) {
+                tail.insert(0, head.clone());
+                out.push(tail);
+            }
+        }
+        out
+    }
+
+    fn spans(&self, rule: usize, from: usize) -> &[usize] {
+        self.ends.get(&(rule, from)).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Tools for using a grammar as a generator instead of a validator.
+impl Grammar {
+    /// Generates every message of at most `max_len` bytes which can be
+    /// derived from `rule`, in sorted order.
+    pub fn enumerate(&self, rule: usize, max_len: usize) -> Vec<String> {
+        let unreachable = self.unreachable(rule);
+        let mut langs: HashMap<usize, BTreeSet<String>> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (&id, alts) in &self.rules {
+                if unreachable.contains(&id) {
+                    continue;
+                }
+                for alt in alts.iter().filter(|alt| Self::is_valid_alt(alt)) {
+                    for message in Self::combine(alt, &langs, max_len) {
+                        changed |= langs.entry(id).or_default().insert(message);
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        langs
+            .remove(&rule)
+            .map(|set| set.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Generates every concatenation of the messages known for `symbols`
+    /// which is no longer than `max_len`.
+    fn combine(
+        symbols: &[Symbol],
+        langs: &HashMap<usize, BTreeSet<String>>,
+        max_len: usize,
+    ) -> Vec<String> {
+        let mut out = vec![String::new()];
+        for symbol in symbols {
+            let options: Vec<&String> = match symbol {
+                Symbol::Literal(lit) => vec![lit],
+                Symbol::Ref(r) => langs.get(r).map_or(Vec::new(), |l| l.iter().collect()),
+            };
+            out = out
+                .iter()
+                .flat_map(|prefix| {
+                    options
+                        .iter()
+                        .filter(move |o| prefix.len() + o.len() <= max_len)
+                        .map(move |o| format!("{}{}", prefix, o))
+                })
+                .collect();
+        }
+        out
+    }
+
+    /// Generates a random message which can be derived from `rule`. Once the
+    /// derivation is `depth` levels deep, only alternatives leading to the
+    /// shortest possible derivations are chosen, so recursive rules always
+    /// finish. Yields `None` if the rule cannot derive anything at all.
+    pub fn sample<R: Rng + ?Sized>(
+        &self,
+        rule: usize,
+        depth: usize,
+        rng: &mut R,
+    ) -> Option<String> {
+        let heights = self.min_heights();
+        heights.get(&rule)?;
+        let mut message = String::new();
+        self.sample_into(rule, depth, &heights, rng, &mut message);
+        Some(message)
+    }
+
+    fn sample_into<R: Rng + ?Sized>(
+        &self,
+        rule: usize,
+        depth: usize,
+        heights: &HashMap<usize, usize>,
+        rng: &mut R,
+        out: &mut String,
+    ) {
+        let mut alts: Vec<(&Vec<Symbol>, usize)> = self.rules[&rule]
+            .iter()
+            .filter_map(|alt| Some((alt, Self::alt_height(alt, heights)?)))
+            .collect();
+        if depth == 0 {
+            let min = heights[&rule];
+            alts.retain(|&(_, h)| h == min);
+        }
+        let &(alt, _) = alts.choose(rng).unwrap();
+        for symbol in alt {
+            match symbol {
+                Symbol::Literal(lit) => out.push_str(lit),
+                Symbol::Ref(r) => self.sample_into(*r, depth.saturating_sub(1), heights, rng, out),
+            }
+        }
+    }
+
+    /// Finds the height of the shortest derivation tree for every rule
+    /// which is able to derive anything at all.
+    fn min_heights(&self) -> HashMap<usize, usize> {
+        let mut heights = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (&id, alts) in &self.rules {
+                let best = alts
+                    .iter()
+                    .filter_map(|alt| Self::alt_height(alt, &heights))
+                    .min();
+                if let Some(h) = best {
+                    if heights.get(&id).is_none_or(|&old| h < old) {
+                        heights.insert(id, h);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        heights
+    }
+
+    /// The height of the shortest derivation using this alternative, if the
+    /// heights of every rule it refers to are already known.
+    fn alt_height(alt: &[Symbol], heights: &HashMap<usize, usize>) -> Option<usize> {
+        if !Self::is_valid_alt(alt) {
+            return None;
+        }
+        let max = alt.iter().try_fold(0, |max, symbol| match symbol {
+            Symbol::Literal(_) => Some(max),
+            Symbol::Ref(r) => Some(max.max(*heights.get(r)?)),
+        })?;
+        Some(max + 1)
+    }
+
+    /// Empty alternatives and empty literals are never matched.
+    fn is_valid_alt(alt: &[Symbol]) -> bool {
+        !alt.is_empty() && !alt.contains(&Symbol::Literal(String::new()))
+    }
+
+    /// Exports every rule as EBNF, e.g. `r0 = r4, r1, r5 ;`.
+    pub fn to_ebnf(&self) -> String {
+        let mut ebnf = String::new();
+        for (id, alts) in &self.rules {
+            let alts: Vec<String> = alts
+                .iter()
+                .map(|alt| {
+                    let symbols: Vec<String> = alt.iter().map(Symbol::to_ebnf).collect();
+                    symbols.join(", ")
+                })
+                .collect();
+            ebnf.push_str(&format!("r{} = {} ;\n", id, alts.join(" | ")));
+        }
+        ebnf
+    }
+
+    /// Exports a rule as an unanchored regular expression, if one exists.
+    /// This works for any rule which isn't recursive, as well as for rules
+    /// which only refer to themselves at the very end of an alternative,
+    /// e.g. `8: 42 | 42 8`. Any other kind of recursion yields `None`, as
+    /// do rules which are unable to derive anything.
+    pub fn to_regex(&self, rule: usize) -> Option<String> {
+        self.regex_of(rule, &mut Vec::new())
+    }
+
+    fn regex_of(&self, rule: usize, stack: &mut Vec<usize>) -> Option<String> {
+        if stack.contains(&rule) {
+            return None;
+        }
+        let alts = self.rules.get(&rule)?;
+        let (rec, base): (Vec<&Vec<Symbol>>, Vec<&Vec<Symbol>>) = alts
+            .iter()
+            .filter(|alt| Self::is_valid_alt(alt))
+            .partition(|alt| alt.last() == Some(&Symbol::Ref(rule)));
+        if base.is_empty() {
+            return None;
+        }
+        stack.push(rule);
+        let rec: Option<Vec<String>> = rec
+            .iter()
+            .map(|alt| self.seq_regex(&alt[..alt.len() - 1], stack))
+            .collect();
+        let base: Option<Vec<String>> = base.iter().map(|alt| self.seq_regex(alt, stack)).collect();
+        stack.pop();
+
+        let (rec, base) = (rec?, base?);
+        let mut regex = String::new();
+        if !rec.is_empty() {
+            regex.push_str(&format!("(?:{})*", rec.join("|")));
+        }
+        regex.push_str(&format!("(?:{})", base.join("|")));
+        Some(regex)
+    }
+
+    fn seq_regex(&self, symbols: &[Symbol], stack: &mut Vec<usize>) -> Option<String> {
+        let mut regex = String::new();
+        for symbol in symbols {
+            match symbol {
+                Symbol::Literal(lit) => regex.push_str(&regex::escape(lit)),
+                Symbol::Ref(r) => regex.push_str(&self.regex_of(*r, stack)?),
+            }
+        }
+        Some(regex)
+    }
+}
+
+/// A summary of the properties of a single rule in a grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleReport {
+    /// The id of the rule being described.
+    pub rule: usize,
+    /// Whether the rule derives a finite number of messages.
+    pub finite: bool,
+    /// The length of the shortest message, if the rule derives anything.
+    pub min_len: Option<usize>,
+    /// The length of the longest message, if the language is finite.
+    pub max_len: Option<usize>,
+    /// The number of distinct messages of each length, up to the bound.
+    pub counts: BTreeMap<usize, usize>,
+    /// Whether the rule can derive itself as its first symbol.
+    pub left_recursive: bool,
+    /// Whether the rule can derive itself as its last symbol.
+    pub right_recursive: bool,
+    /// Whether the rule can derive itself anywhere at all.
+    pub recursive: bool,
+    /// A message within the bound with more than one derivation, if any.
+    pub ambiguous: Option<String>,
+    /// Whether the bound covered every message the rule can derive, in
+    /// which case the counts and ambiguity check are exact.
+    pub exhaustive: bool,
+}
+
+impl RuleReport {
+    /// The length of every message, if they are all the same length.
+    pub fn fixed_len(&self) -> Option<usize> {
+        match (self.min_len, self.max_len) {
+            (Some(min), Some(max)) if min == max => Some(min),
+            _ => None,
+        }
+    }
+}
+
+/// Describes the report in a single line, e.g.
+/// `Rule 42: finite, always 8 long; {8: 128}; not recursive; unambiguous`.
+impl Display for RuleReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Rule {}: ", self.rule)?;
+        match (self.min_len, self.max_len, self.fixed_len()) {
+            (None, _, _) => return write!(f, "derives nothing"),
+            (_, _, Some(len)) => write!(f, "finite, always {} long", len)?,
+            (Some(min), Some(max), None) => write!(f, "finite, {} to {} long", min, max)?,
+            (Some(min), None, None) => write!(f, "infinite, at least {} long", min)?,
+        }
+        write!(f, "; {:?}; ", self.counts)?;
+        let recursion = match (self.left_recursive, self.right_recursive, self.recursive) {
+            (true, true, _) => "left- and right-recursive",
+            (true, false, _) => "left-recursive",
+            (false, true, _) => "right-recursive",
+            (false, false, true) => "recursive",
+            (false, false, false) => "not recursive",
+        };
+        write!(f, "{}; ", recursion)?;
+        match (&self.ambiguous, self.exhaustive) {
+            (Some(message), _) => write!(f, "ambiguous for {:?}", message),
+            (None, true) => write!(f, "unambiguous"),
+            (None, false) => write!(f, "no ambiguity within the bound"),
+        }
+    }
+}
+
+/// Tools for analyzing the languages described by a grammar.
+impl Grammar {
+    /// Analyzes every rule in the grammar. Messages are only counted and
+    /// checked for ambiguity up to `bound` bytes long.
+    pub fn analyze(&self, bound: usize) -> Vec<RuleReport> {
+        let graph = RuleGraph::new(self);
+        self.rules
+            .keys()
+            .map(|&id| self.analyze_with(&graph, id, bound))
+            .collect()
+    }
+
+    /// Analyzes a single rule. See [`Self::analyze`].
+    pub fn analyze_rule(&self, rule: usize, bound: usize) -> RuleReport {
+        self.analyze_with(&RuleGraph::new(self), rule, bound)
+    }
+
+    fn analyze_with(&self, graph: &RuleGraph, rule: usize, bound: usize) -> RuleReport {
+        let min_len = graph.min_lens.get(&rule).copied();
+        let max_len = graph.max_lens.get(&rule).copied();
+        let messages = self.enumerate(rule, bound);
+        let mut counts = BTreeMap::new();
+        for message in &messages {
+            *counts.entry(message.len()).or_insert(0) += 1;
+        }
+        let ambiguous = messages
+            .into_iter()
+            .find(|m| self.derivations(rule, m).len() > 1);
+        RuleReport {
+            rule,
+            finite: min_len.is_none() || graph.finite.contains(&rule),
+            min_len,
+            max_len,
+            counts,
+            left_recursive: graph.reaches(&graph.first, rule, rule),
+            right_recursive: graph.reaches(&graph.last, rule, rule),
+            recursive: graph.reaches(&graph.all, rule, rule),
+            ambiguous,
+            exhaustive: max_len.map_or(min_len.is_none(), |max| max <= bound),
+        }
+    }
+}
+
+/// The relationships between the rules in a grammar, ignoring any
+/// alternatives which can never derive anything.
+struct RuleGraph {
+    /// Every rule referred to by each rule.
+    all: HashMap<usize, Vec<usize>>,
+    /// The rules referred to by the first symbol of each alternative.
+    first: HashMap<usize, Vec<usize>>,
+    /// The rules referred to by the last symbol of each alternative.
+    last: HashMap<usize, Vec<usize>>,
+    /// References which make the message longer when followed, i.e.
+    /// those coming from alternatives with more than one symbol.
+    growing: Vec<(usize, usize)>,
+    /// Every rule which derives a finite number of messages.
+    finite: HashSet<usize>,
+    /// The length of the shortest message derived by each rule.
+    min_lens: HashMap<usize, usize>,
+    /// The length of the longest message derived by each finite rule.
+    max_lens: HashMap<usize, usize>,
+}
+
+impl RuleGraph {
+    fn new(grammar: &Grammar) -> Self {
+        let heights = grammar.min_heights();
+        let mut graph = Self {
+            all: HashMap::new(),
+            first: HashMap::new(),
+            last: HashMap::new(),
+            growing: Vec::new(),
+            finite: HashSet::new(),
+            min_lens: HashMap::new(),
+            max_lens: HashMap::new(),
+        };
+        for (&id, alts) in &grammar.rules {
+            for alt in alts {
+                if Grammar::alt_height(alt, &heights).is_none() {
+                    continue;
+                }
+                let refs = alt.iter().filter_map(Symbol::rule_id);
+                graph.all.entry(id).or_default().extend(refs.clone());
+                if let Some(r) = alt[0].rule_id() {
+                    graph.first.entry(id).or_default().push(r);
+                }
+                if let Some(r) = alt[alt.len() - 1].rule_id() {
+                    graph.last.entry(id).or_default().push(r);
+                }
+                if alt.len() > 1 {
+                    graph.growing.extend(refs.map(|r| (id, r)));
+                }
+            }
+        }
+        let all: HashSet<usize> = heights.keys().copied().collect();
+        graph.finite = heights
+            .keys()
+            .copied()
+            .filter(|&id| !graph.reaches_growing_cycle(id))
+            .collect();
+        graph.min_lens = Self::fold_lens(grammar, &heights, &all, |a, b| a < b);
+        graph.max_lens = Self::fold_lens(grammar, &heights, &graph.finite, |a, b| a > b);
+        graph
+    }
+
+    /// Determines whether `to` can be reached from `from` in at least one step.
+    fn reaches(&self, edges: &HashMap<usize, Vec<usize>>, from: usize, to: usize) -> bool {
+        let mut seen = HashSet::new();
+        let mut queue: Vec<usize> = edges.get(&from).cloned().unwrap_or_default();
+        while let Some(id) = queue.pop() {
+            if id == to {
+                return true;
+            }
+            if seen.insert(id) {
+                queue.extend(edges.get(&id).into_iter().flatten());
+            }
+        }
+        false
+    }
+
+    /// Determines whether a rule can reach a cycle which makes messages
+    /// longer each time it is followed, i.e. whether it is infinite.
+    fn reaches_growing_cycle(&self, rule: usize) -> bool {
+        self.growing.iter().any(|&(a, b)| {
+            (a == rule || self.reaches(&self.all, rule, a))
+                && (b == a || self.reaches(&self.all, b, a))
+        })
+    }
+
+    /// Finds the best total length of each rule in `rules` according to
+    /// `better`. Finite rules never refer to infinite ones, so searching
+    /// for the longest messages in finite rules always settles.
+    fn fold_lens<F: Fn(usize, usize) -> bool>(
+        grammar: &Grammar,
+        heights: &HashMap<usize, usize>,
+        rules: &HashSet<usize>,
+        better: F,
+    ) -> HashMap<usize, usize> {
+        let mut lens: HashMap<usize, usize> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for &id in rules {
+                let alts = grammar.rules[&id].iter();
+                for alt in alts.filter(|a| Grammar::alt_height(a, heights).is_some()) {
+                    let len = alt.iter().try_fold(0, |sum, symbol| match symbol {
+                        Symbol::Literal(lit) => Some(sum + lit.len()),
+                        Symbol::Ref(r) => Some(sum + *lens.get(r)?),
+                    });
+                    if let Some(len) = len {
+                        if lens.get(&id).is_none_or(|&old| better(len, old)) {
+                            lens.insert(id, len);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if !changed {
+                return lens;
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::{Index, IndexMut};
+
+/// The four directions sharing an edge with any cell as `(x, y)`.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// The eight directions surrounding any cell as `(x, y)`.
+pub const ADJACENT: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular grid of values, stored one row after another. Positions
+/// are always `(x, y)`, starting from the top left corner.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Constructs a new grid with every cell set to `value`.
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Constructs a new grid by calling `f` with every position.
+    pub fn from_fn<F: FnMut(usize, usize) -> T>(width: usize, height: usize, mut f: F) -> Self {
+        let cells = (0..width * height)
+            .map(|i| f(i % width, i / width))
+            .collect();
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Constructs a new grid from a list of rows. Yields `None` if they
+    /// are not all the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        Some(Self {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Maps every character in the text to a cell, one line per row.
+    /// Panics if the lines are not all the same length.
+    pub fn parse<F: Fn(char) -> T>(s: &str, mapper: F) -> Self {
+        let rows = s.lines().map(|l| l.chars().map(&mapper).collect());
+        Self::from_rows(rows.collect()).expect("Every line must be the same length.")
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The total number of cells.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Retrieves the value at `(x, y)`, if it is in bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.cells.get(self.index_of(x, y)?)
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        let i = self.index_of(x, y)?;
+        self.cells.get_mut(i)
+    }
+
+    /// Retrieves the value at `(x, y)` as if the grid repeated forever in
+    /// every direction. Panics if the grid is empty.
+    pub fn get_wrapping(&self, x: isize, y: isize) -> &T {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        &self[(x, y)]
+    }
+
+    /// The position of `(x + dx, y + dy)`, if it is in bounds.
+    pub fn offset(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let x = x as isize + dx;
+        let y = y as isize + dy;
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    /// The position of `(x, y)` in the underlying storage, if it is in
+    /// bounds.
+    pub fn index_of(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // Empty grids still need a non-zero chunk size.
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Every value in column `x`, from top to bottom.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "Column out of bounds.");
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// Every value, one row after another.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    /// Every value along with its position.
+    pub fn positions(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        let cells = self.cells.iter().enumerate();
+        cells.map(move |(i, c)| ((i % width, i / width), c))
+    }
+
+    /// The positions sharing an edge with `(x, y)` which are in bounds.
+    pub fn neighbors4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        ORTHOGONAL
+            .iter()
+            .filter_map(move |&(dx, dy)| self.offset(x, y, dx, dy))
+    }
+
+    /// The positions surrounding `(x, y)` which are in bounds, including
+    /// diagonals.
+    pub fn neighbors8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        ADJACENT
+            .iter()
+            .filter_map(move |&(dx, dy)| self.offset(x, y, dx, dy))
+    }
+
+    /// Every position from `(x, y)` moving by `(dx, dy)` at a time until
+    /// leaving the grid. The starting position is not included.
+    pub fn ray(
+        &self,
+        x: usize,
+        y: usize,
+        dx: isize,
+        dy: isize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut current = Some((x, y)).filter(|_| dx != 0 || dy != 0);
+        std::iter::from_fn(move || {
+            let (x, y) = current?;
+            current = self.offset(x, y, dx, dy);
+            current
+        })
+    }
+
+    /// Generates a new grid of the same size by mapping every value.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Copies every row into a separate `Vec`.
+    #[cfg(test)]
+    pub fn to_rows(&self) -> Vec<Vec<T>>
+    where
+        T: Clone,
+    {
+        self.rows().map(<[T]>::to_vec).collect()
+    }
+}
+
+/// Retrieves the value at `(x, y)`. Panics when out of bounds.
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).expect("Index out of bounds.")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x, y).expect("Index out of bounds.")
+    }
+}
+
+#[test]
+fn test_access() {
+    let mut grid = Grid::parse("123\n456", |c| c.to_digit(10).unwrap());
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert_eq!(grid[(2, 1)], 6);
+    assert_eq!(grid.get(3, 0), None);
+    assert_eq!(*grid.get_wrapping(-1, 5), 6);
+    assert_eq!(grid.row(1), &[4, 5, 6]);
+    assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+    grid[(0, 0)] = 7;
+    assert_eq!(grid.to_rows(), vec![vec![7, 2, 3], vec![4, 5, 6]]);
+    assert!(Grid::from_rows(vec![vec![1, 2], vec![3]]).is_none());
+}
+
+#[test]
+fn test_neighbors() {
+    let grid = Grid::new(4, 3, ());
+    assert_eq!(
+        grid.neighbors4(0, 0).collect::<Vec<_>>(),
+        vec![(1, 0), (0, 1)]
+    );
+    assert_eq!(grid.neighbors8(0, 0).count(), 3);
+    assert_eq!(grid.neighbors8(1, 1).count(), 8);
+    let ray: Vec<_> = grid.ray(0, 2, 1, -1).collect();
+    assert_eq!(ray, vec![(1, 1), (2, 0)]);
+    assert_eq!(grid.ray(1, 1, 0, 0).count(), 0);
+}
@@ -1,6 +1,15 @@
+mod animate;
+mod automaton;
 mod computer;
+mod export;
+mod grammar;
+mod grid;
+mod navigation;
+mod number_theory;
 mod solution_template;
 mod solutions;
+mod symmetry;
+mod tools;
 
 use futures::executor::block_on;
 use futures::future::join_all;
@@ -27,6 +36,10 @@ use solutions::trees::TreeSolution;
 use solutions::xmas::XmasSolution;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if tools::run(&args) {
+        return;
+    }
     block_on(join_all(vec![
         Sum2020Solution::run("2020_sums.txt"),
         PasswordSolution::run("passwords.txt"),
@@ -0,0 +1,390 @@
+use std::fmt::{self, Display, Formatter, Write};
+use std::ops::{Add, Mul, Sub};
+
+/// The color of the ship's route.
+const SHIP_COLOR: &str = "#1f5fbf";
+/// The color of the waypoint's trail.
+const WAYPOINT_COLOR: &str = "#e07020";
+
+/// A position or direction on the water, measured east and north.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Vector {
+    pub east: f64,
+    pub north: f64,
+}
+
+impl Vector {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+
+    pub const fn new(east: f64, north: f64) -> Self {
+        Self { east, north }
+    }
+
+    /// Rotates clockwise by `degrees`, e.g. turning east into south at 90.
+    /// Multiples of 90 degrees are exact.
+    pub fn rotate(self, degrees: f64) -> Self {
+        let (sin, cos) = match degrees.rem_euclid(360.0) {
+            0.0 => (0.0, 1.0),
+            90.0 => (1.0, 0.0),
+            180.0 => (0.0, -1.0),
+            270.0 => (-1.0, 0.0),
+            d => d.to_radians().sin_cos(),
+        };
+        Self::new(
+            self.east * cos + self.north * sin,
+            self.north * cos - self.east * sin,
+        )
+    }
+
+    pub fn length(self) -> f64 {
+        self.east.hypot(self.north)
+    }
+
+    pub fn manhattan(self) -> f64 {
+        self.east.abs() + self.north.abs()
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.east * other.east + self.north * other.north
+    }
+}
+
+/// Writes the vector as `(east, north)`.
+impl Display for Vector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.east, self.north)
+    }
+}
+
+impl Add for Vector {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.east + other.east, self.north + other.north)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.east - other.east, self.north - other.north)
+    }
+}
+
+impl Mul<f64> for Vector {
+    type Output = Self;
+
+    fn mul(self, amount: f64) -> Self {
+        Self::new(self.east * amount, self.north * amount)
+    }
+}
+
+/// What the ship steers by.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// The ship turns and moves itself.
+    Heading,
+    /// The ship follows a waypoint, which turns and moves around it.
+    Waypoint,
+}
+
+/// Steers a ship, recording every position along the way.
+#[derive(Debug, Clone)]
+pub struct Navigator {
+    mode: Mode,
+    ship: Vector,
+    /// The direction the ship faces, or the position of the waypoint
+    /// relative to the ship.
+    target: Vector,
+    route: Route,
+}
+
+impl Navigator {
+    /// Constructs a new navigator at the origin. Ships start facing east
+    /// and waypoints start 10 units east and 1 unit north of the ship.
+    pub fn new(mode: Mode) -> Self {
+        let target = match mode {
+            Mode::Heading => Vector::new(1.0, 0.0),
+            Mode::Waypoint => Vector::new(10.0, 1.0),
+        };
+        let mut navigator = Self {
+            mode,
+            ship: Vector::ZERO,
+            target,
+            route: Route::default(),
+        };
+        navigator.record();
+        navigator
+    }
+
+    pub fn ship(&self) -> Vector {
+        self.ship
+    }
+
+    /// The direction the ship faces, or the position of the waypoint
+    /// relative to the ship.
+    pub fn target(&self) -> Vector {
+        self.target
+    }
+
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+
+    /// Moves the ship or, when following a waypoint, the waypoint.
+    pub fn shift(&mut self, offset: Vector) {
+        match self.mode {
+            Mode::Heading => self.ship = self.ship + offset,
+            Mode::Waypoint => self.target = self.target + offset,
+        }
+        self.record();
+    }
+
+    /// Turns the ship, or the waypoint around the ship, clockwise.
+    pub fn turn(&mut self, degrees: f64) {
+        self.target = self.target.rotate(degrees);
+        self.record();
+    }
+
+    /// Moves the ship toward its target `amount` times.
+    pub fn forward(&mut self, amount: f64) {
+        self.ship = self.ship + self.target * amount;
+        self.record();
+    }
+
+    fn record(&mut self) {
+        self.route.ship.push(self.ship);
+        if self.mode == Mode::Waypoint {
+            self.route.waypoints.push(self.ship + self.target);
+        }
+    }
+}
+
+/// Every position of a ship, and its waypoint if it had one, from the
+/// start and after every step.
+#[derive(Debug, Clone, Default)]
+pub struct Route {
+    ship: Vec<Vector>,
+    waypoints: Vec<Vector>,
+}
+
+impl Route {
+    /// Every position of the ship, starting from the origin.
+    pub fn ship(&self) -> &[Vector] {
+        &self.ship
+    }
+
+    /// Every absolute position of the waypoint. Empty if there was none.
+    pub fn waypoints(&self) -> &[Vector] {
+        &self.waypoints
+    }
+
+    /// The total distance sailed by the ship.
+    pub fn distance(&self) -> f64 {
+        let legs = self.ship.windows(2);
+        legs.map(|leg| (leg[1] - leg[0]).length()).sum()
+    }
+
+    /// The corners of the smallest box containing every position of the
+    /// ship as `(south west, north east)`.
+    pub fn bounds(&self) -> Option<(Vector, Vector)> {
+        let first = *self.ship.first()?;
+        let corners = self.ship.iter().fold((first, first), |(min, max), p| {
+            (
+                Vector::new(min.east.min(p.east), min.north.min(p.north)),
+                Vector::new(max.east.max(p.east), max.north.max(p.north)),
+            )
+        });
+        Some(corners)
+    }
+
+    /// The point closest to the origin that the ship passes through,
+    /// including between steps. Ships start at the origin, so only the
+    /// route after the ship first moves counts. Turns and waypoint moves
+    /// before then leave the ship where it started.
+    pub fn closest_approach(&self) -> Option<Vector> {
+        let start = *self.ship.first()?;
+        let moved = self.ship.iter().position(|&p| p != start)?;
+        let rest = &self.ship[moved..];
+        let closest = |a: Vector, b: Vector| {
+            let leg = b - a;
+            let len = leg.dot(leg);
+            let t = if len == 0.0 {
+                0.0
+            } else {
+                (-a.dot(leg) / len).clamp(0.0, 1.0)
+            };
+            a + leg * t
+        };
+        let points = rest.windows(2).map(|leg| closest(leg[0], leg[1]));
+        let points = points.chain(std::iter::once(rest[0]));
+        points.min_by(|a, b| a.length().total_cmp(&b.length()))
+    }
+
+    /// Draws the ship's route, and the waypoint's trail if there is one.
+    /// Every position after the start is labeled with its step number,
+    /// followed by the matching label, if any. North is up.
+    pub fn to_svg<S: AsRef<str>>(&self, labels: &[S]) -> String {
+        let all = self.ship.iter().chain(&self.waypoints);
+        let (min, max) = match all.clone().next() {
+            Some(&first) => all.fold((first, first), |(min, max), p| {
+                (
+                    Vector::new(min.east.min(p.east), min.north.min(p.north)),
+                    Vector::new(max.east.max(p.east), max.north.max(p.north)),
+                )
+            }),
+            None => (Vector::ZERO, Vector::ZERO),
+        };
+        let size = (max.east - min.east).max(max.north - min.north).max(1.0);
+        let (margin, stroke, font) = (size / 20.0, size / 400.0, size / 60.0);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            min.east - margin,
+            svg_y(max.north) - margin,
+            max.east - min.east + 2.0 * margin,
+            max.north - min.north + 2.0 * margin
+        )
+        .unwrap();
+        if !self.waypoints.is_empty() {
+            writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-dasharray="{}"/>"#,
+                points(&self.waypoints),
+                WAYPOINT_COLOR,
+                stroke,
+                stroke * 4.0
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+            points(&self.ship),
+            SHIP_COLOR,
+            stroke * 2.0
+        )
+        .unwrap();
+        for (step, p) in self.ship.iter().enumerate() {
+            writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                p.east,
+                svg_y(p.north),
+                stroke * 3.0,
+                SHIP_COLOR
+            )
+            .unwrap();
+            if step == 0 {
+                continue;
+            }
+            let label = match labels.get(step - 1) {
+                Some(label) => format!("{}: {}", step, escape_xml(label.as_ref())),
+                None => step.to_string(),
+            };
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="{}">{}</text>"#,
+                p.east + font / 2.0,
+                svg_y(p.north) - font / 2.0,
+                font,
+                label
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Escapes the characters which would otherwise be read as markup.
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// SVG coordinates point down. Subtracting avoids drawing `-0`.
+fn svg_y(north: f64) -> f64 {
+    0.0 - north
+}
+
+/// Formats positions for an SVG polyline, flipping north to point up.
+fn points(positions: &[Vector]) -> String {
+    let points: Vec<String> = positions
+        .iter()
+        .map(|p| format!("{},{}", p.east, svg_y(p.north)))
+        .collect();
+    points.join(" ")
+}
+
+#[test]
+fn test_rotate() {
+    let east = Vector::new(1.0, 0.0);
+    assert_eq!(east.rotate(90.0), Vector::new(0.0, -1.0));
+    assert_eq!(east.rotate(-90.0), Vector::new(0.0, 1.0));
+    assert_eq!(east.rotate(720.0), east);
+    let diagonal = east.rotate(45.0);
+    assert!((diagonal.east - 0.5f64.sqrt()).abs() < 1e-12);
+    assert!((diagonal.north + 0.5f64.sqrt()).abs() < 1e-12);
+}
+
+#[test]
+fn test_route() {
+    let mut navigator = Navigator::new(Mode::Heading);
+    navigator.shift(Vector::new(0.0, 3.0));
+    navigator.forward(4.0);
+    navigator.turn(180.0);
+    navigator.forward(8.0);
+    let route = navigator.route();
+    assert_eq!(route.ship().len(), 5);
+    assert!(route.waypoints().is_empty());
+    assert_eq!(route.distance(), 15.0);
+    let (min, max) = route.bounds().unwrap();
+    assert_eq!((min, max), (Vector::new(-4.0, 0.0), Vector::new(4.0, 3.0)));
+    assert_eq!(route.closest_approach(), Some(Vector::new(0.0, 3.0)));
+
+    let mut navigator = Navigator::new(Mode::Waypoint);
+    navigator.forward(1.0);
+    navigator.turn(-90.0);
+    assert_eq!(navigator.route().waypoints()[2], Vector::new(9.0, 11.0));
+    assert_eq!(
+        Navigator::new(Mode::Heading).route().closest_approach(),
+        None
+    );
+
+    // The ship stays at the origin until it first moves forward.
+    let mut navigator = Navigator::new(Mode::Waypoint);
+    navigator.turn(90.0);
+    navigator.shift(Vector::new(0.0, -2.0));
+    assert_eq!(navigator.route().closest_approach(), None);
+    navigator.forward(1.0);
+    navigator.forward(1.0);
+    assert_eq!(
+        navigator.route().closest_approach(),
+        Some(Vector::new(1.0, -12.0))
+    );
+}
+
+#[test]
+fn test_svg() {
+    let mut navigator = Navigator::new(Mode::Waypoint);
+    navigator.forward(2.0);
+    navigator.shift(Vector::new(0.0, -5.0));
+    let svg = navigator.route().to_svg(&["F2", "<b> & c"]);
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(r#"points="0,0 20,-2 20,-2""#));
+    assert!(svg.contains(r#"points="10,-1 30,-3 30,2""#));
+    assert!(svg.contains(">1: F2</text>"));
+    assert!(svg.contains(">2: &lt;b&gt; &amp; c</text>"));
+    assert_eq!(svg.matches("<circle").count(), 3);
+}
@@ -0,0 +1,263 @@
+use num_integer::Integer;
+use num_traits::{CheckedMul, Signed};
+
+#[cfg(test)]
+use num_bigint::BigInt;
+
+/// Any signed integer, e.g. `i64`, `i128` or `BigInt`.
+pub trait Number: Integer + Signed + CheckedMul + Clone {}
+
+impl<T: Integer + Signed + CheckedMul + Clone> Number for T {}
+
+/// Every number equal to `residue` modulo `modulus`. The residue is always
+/// in `0..modulus`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Congruence<T> {
+    residue: T,
+    modulus: T,
+}
+
+impl<T: Number> Congruence<T> {
+    /// Constructs a new congruence, wrapping the residue into range. Yields
+    /// `None` if the modulus is not positive.
+    pub fn new(residue: T, modulus: T) -> Option<Self> {
+        if !modulus.is_positive() {
+            return None;
+        }
+        Some(Self {
+            residue: residue.mod_floor(&modulus),
+            modulus,
+        })
+    }
+
+    /// The congruence satisfied by every number.
+    pub fn any() -> Self {
+        Self {
+            residue: T::zero(),
+            modulus: T::one(),
+        }
+    }
+
+    pub fn residue(&self) -> &T {
+        &self.residue
+    }
+
+    pub fn modulus(&self) -> &T {
+        &self.modulus
+    }
+
+    /// The smallest number satisfying this congruence which is at least
+    /// `min`.
+    pub fn first_from(&self, min: &T) -> T {
+        let offset = (self.residue.clone() - min.clone()).mod_floor(&self.modulus);
+        min.clone() + offset
+    }
+
+    /// Merges two congruences, yielding the kind of error on failure so
+    /// the caller can say which congruence caused it.
+    fn combine(&self, other: &Self) -> Result<Self, fn(usize) -> CrtError> {
+        let g = self.modulus.gcd(&other.modulus);
+        let diff = other.residue.clone() - self.residue.clone();
+        if !diff.is_multiple_of(&g) {
+            return Err(CrtError::Inconsistent);
+        }
+        // x = r1 + m1 * t, where m1 * t = r2 - r1 (mod m2). Dividing
+        // through by the gcd leaves m1 / g invertible.
+        let step = other.modulus.clone() / g.clone();
+        let p = mod_inv(&(self.modulus.clone() / g.clone()), &step)
+            .expect("Dividing by the gcd leaves coprime moduli.");
+        let modulus = match self.modulus.checked_mul(&step) {
+            Some(modulus) => modulus,
+            None => return Err(CrtError::Overflow),
+        };
+        let t = mul_mod(&(diff / g).mod_floor(&step), &p, &step);
+        // Both terms are below the new modulus, so neither can overflow.
+        let residue = self.residue.clone() + self.modulus.clone() * t;
+        Ok(Self { residue, modulus })
+    }
+}
+
+/// A reason why a system of congruences can't be solved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CrtError {
+    /// The congruence at this index contradicts the ones before it.
+    Inconsistent(usize),
+    /// Merging the congruence at this index doesn't fit in the type.
+    Overflow(usize),
+}
+
+/// Solves a system of congruences using the generalized Chinese remainder
+/// theorem, yielding every solution as a single congruence. The moduli do
+/// not need to be coprime.
+pub fn solve<T, I>(congruences: I) -> Result<Congruence<T>, CrtError>
+where
+    T: Number,
+    I: IntoIterator<Item = Congruence<T>>,
+{
+    let mut congruences = congruences.into_iter().enumerate();
+    congruences.try_fold(Congruence::any(), |acc, (i, c)| {
+        acc.combine(&c).map_err(|e| e(i))
+    })
+}
+
+/// Yields `(g, x, y)` such that `a * x + b * y = g`, where `g` is the
+/// greatest common divisor of `a` and `b`. `g` is never negative.
+pub fn egcd<T: Number>(a: &T, b: &T) -> (T, T, T) {
+    let (mut old_r, mut r) = (a.clone(), b.clone());
+    let (mut old_x, mut x) = (T::one(), T::zero());
+    let (mut old_y, mut y) = (T::zero(), T::one());
+    while !r.is_zero() {
+        let q = old_r.div_floor(&r);
+        let next_r = old_r - q.clone() * r.clone();
+        let next_x = old_x - q.clone() * x.clone();
+        let next_y = old_y - q * y.clone();
+        old_r = std::mem::replace(&mut r, next_r);
+        old_x = std::mem::replace(&mut x, next_x);
+        old_y = std::mem::replace(&mut y, next_y);
+    }
+    if old_r.is_negative() {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// `a * b` modulo `m`, where both are already in `0..m`. Falls back on
+/// doubling when the product itself doesn't fit, so this never overflows.
+fn mul_mod<T: Number>(a: &T, b: &T, m: &T) -> T {
+    if let Some(product) = a.checked_mul(b) {
+        return product.mod_floor(m);
+    }
+    // Adds without ever going over `m`.
+    let add = |x: T, y: T| {
+        let room = m.clone() - y.clone();
+        if x >= room {
+            x - room
+        } else {
+            x + y
+        }
+    };
+    let two = T::one() + T::one();
+    let (mut a, mut b, mut product) = (a.clone(), b.clone(), T::zero());
+    while !b.is_zero() {
+        if b.is_odd() {
+            product = add(product, a.clone());
+        }
+        a = add(a.clone(), a);
+        b = b / two.clone();
+    }
+    product
+}
+
+/// The number `y` in `0..n` such that `x * y = 1 (mod n)`. Yields `None`
+/// if `x` and `n` are not coprime or `n` is not positive.
+pub fn mod_inv<T: Number>(x: &T, n: &T) -> Option<T> {
+    if !n.is_positive() {
+        return None;
+    }
+    let (g, y, _) = egcd(&x.mod_floor(n), n);
+    if g.is_one() {
+        Some(y.mod_floor(n))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+fn brute_force(congruences: &[(i64, i64)]) -> Option<i64> {
+    let lcm = congruences.iter().fold(1, |acc, &(_, m)| acc.lcm(&m));
+    (0..lcm).find(|x| {
+        congruences
+            .iter()
+            .all(|&(r, m)| x.mod_floor(&m) == r.mod_floor(&m))
+    })
+}
+
+#[test]
+fn test_inverse() {
+    assert_eq!(egcd(&240, &46), (2, -9, 47));
+    assert_eq!(egcd(&-4, &6).0, 2);
+    assert_eq!(mod_inv(&3, &7), Some(5));
+    assert_eq!(mod_inv(&-3, &7), Some(2));
+    assert_eq!(mod_inv(&4, &6), None);
+    assert_eq!(mod_inv(&4, &0), None);
+    assert_eq!(mod_inv(&4, &1), Some(0));
+}
+
+#[test]
+fn test_solve() {
+    let c = |r, m| Congruence::new(r, m).unwrap();
+    let solved = solve(vec![c(2i128, 6), c(8, 10)]).unwrap();
+    assert_eq!((*solved.residue(), *solved.modulus()), (8, 30));
+    assert_eq!(solved.first_from(&9), 38);
+    assert_eq!(
+        solve(vec![c(0, 7), c(1, 4), c(2, 6)]),
+        Err(CrtError::Inconsistent(2))
+    );
+    assert_eq!(solve(Vec::<Congruence<i128>>::new()), Ok(Congruence::any()));
+    assert!(Congruence::new(1, 0).is_none());
+
+    // These primes are too big to combine in an i128, but not a BigInt.
+    let primes = [(1u128 << 89) - 1, (1u128 << 107) - 1];
+    let wide = primes.iter().map(|&p| c(1, p as i128));
+    assert_eq!(solve(wide), Err(CrtError::Overflow(1)));
+    let big = primes
+        .iter()
+        .map(|&p| Congruence::new(BigInt::from(1), BigInt::from(p)).unwrap());
+    let solved = solve(big).unwrap();
+    assert_eq!(*solved.residue(), BigInt::from(1));
+    assert_eq!(*solved.modulus(), BigInt::from(primes[0]) * primes[1]);
+
+    // The combined modulus fits, even though the steps along the way don't.
+    let p = (1i128 << 100) + 1;
+    let solved = solve(vec![c(1, 3), c(p - 5, p)]).unwrap();
+    assert_eq!(*solved.modulus(), 3 * p);
+    let x = *solved.residue();
+    assert_eq!((x % 3, x % p), (1, p - 5));
+    assert_eq!(
+        mul_mod(&(p - 1), &(p - 2), &p),
+        2,
+        "(-1) * (-2) = 2 (mod p)"
+    );
+}
+
+#[test]
+fn test_brute_force() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(46);
+    for _ in 0..500 {
+        let len = rng.gen_range(1, 4);
+        let pairs: Vec<(i64, i64)> = (0..len)
+            .map(|_| (rng.gen_range(-30, 30), rng.gen_range(1, 25)))
+            .collect();
+        let expected = brute_force(&pairs);
+        let small = pairs
+            .iter()
+            .map(|&(r, m)| Congruence::new(r as i128, m as i128));
+        let big = pairs
+            .iter()
+            .map(|&(r, m)| Congruence::new(BigInt::from(r), BigInt::from(m)));
+        let small = solve(small.map(Option::unwrap));
+        let big = solve(big.map(Option::unwrap));
+        match expected {
+            Some(x) => {
+                let lcm = pairs.iter().fold(1, |acc, &(_, m)| acc.lcm(&m));
+                let small = small.unwrap();
+                assert_eq!(
+                    (*small.residue(), *small.modulus()),
+                    (x as i128, lcm as i128)
+                );
+                let big = big.unwrap();
+                assert_eq!(*big.residue(), BigInt::from(x), "{:?}", pairs);
+            }
+            None => {
+                assert!(
+                    matches!(small, Err(CrtError::Inconsistent(_))),
+                    "{:?}",
+                    pairs
+                );
+                assert_eq!(small.unwrap_err(), big.unwrap_err());
+            }
+        }
+    }
+}
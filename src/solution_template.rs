@@ -5,6 +5,8 @@ use std::process;
 
 use async_trait::async_trait;
 
+use crate::grid::Grid;
+
 /// A setting indicating a constant length for each message output.
 const MESSAGE_LENGTH: usize = 40;
 
@@ -41,11 +43,9 @@ pub trait Solution {
     }
 
     /// A utility function for mapping every single character in a file
-    /// into a `Vec<Vec<T>>`, where nested arrays correspond to each line.
-    fn map_chars<T, F: Fn(char) -> T>(s: &str, mapper: F) -> Vec<Vec<T>> {
-        s.lines()
-            .map(|line| line.chars().map(&mapper).collect())
-            .collect()
+    /// into a `Grid<T>`, where each row corresponds to a line.
+    fn map_chars<T, F: Fn(char) -> T>(s: &str, mapper: F) -> Grid<T> {
+        Grid::parse(s, mapper)
     }
 
     /// Generates a new set of data from a file.
@@ -1,9 +1,21 @@
+use crate::animate::{self, Options};
+use crate::automaton::Automaton;
+use crate::export::Picture;
+use crate::grid::Grid;
 use crate::solution_template::Solution;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
 
 pub struct ConwaySolution;
 
-pub type Cube = Vec<Vec<Vec<bool>>>;
-pub type Time = Vec<Cube>;
+/// The rule used by the puzzle in B/S notation.
+const RULE: &str = "B3/S23";
+
+/// The starting plane from the puzzle's example.
+const EXAMPLE: &str = ".#.\n..#\n###";
+
+/// A coordinate in any number of dimensions.
+pub type Point = Vec<i32>;
 
 /// Elves have a new power source contains an infinite, multidimensional
 /// array of cubes which may be active or inactive. Those cubes change
@@ -11,37 +23,103 @@ pub type Time = Vec<Cube>;
 /// or inactive. You're creating a simulation to help the elves study
 /// this new technology.
 impl Solution for ConwaySolution {
-    type Data = Cube;
-    type Output = u32;
+    type Data = Grid<bool>;
+    type Output = usize;
 
     const MESSAGE_A: &'static str = "Num cubes (3D)";
     const MESSAGE_B: &'static str = "Num cubes (4D)";
 
-    fn from_string(s: &str) -> Cube {
-        vec![Self::map_chars(s, is_active)]
+    fn from_string(s: &str) -> Grid<bool> {
+        Self::map_chars(s, is_active)
     }
 
     /// Find the number of cubes inside of the box after 6 cycles.
-    fn get_solution_a(data: &Cube) -> Option<u32> {
-        // Expand this to be more than large enough
-        // so we don't need boundary checks.
-        let mut cube = expand(data, data.len() + 21);
-        for _ in 0..6 {
-            cube = cycle(&cube);
-        }
-        Some(count_total(&cube))
+    fn get_solution_a(data: &Grid<bool>) -> Option<usize> {
+        Some(Space::from_plane(data, 3).with_rule(rule()).count_after(6))
     }
 
     /// Find the number of cubes after 6 cycles in 4 dimensions.
-    fn get_solution_b(data: &Cube) -> Option<u32> {
-        let new_len = data.len() + 21;
-        let mut time = get_time(new_len);
-        time[new_len / 2] = expand(data, new_len);
-        for _ in 0..6 {
-            time = cycle_4d(&time);
-        }
-        Some(count_total_4d(&time))
+    fn get_solution_b(data: &Grid<bool>) -> Option<usize> {
+        Some(Space::from_plane(data, 4).with_rule(rule()).count_after(6))
+    }
+}
+
+fn rule() -> Rule {
+    Rule::parse(RULE).expect("Invalid rule")
+}
+
+/// Animates the puzzle in 3 dimensions, or 4 for part b, drawing a
+/// single slice through the extra axes.
+pub fn animate(options: &Options) -> Result<(), String> {
+    let (space, slice) = setup(options.input, options.part_b, options.slice.clone())?;
+    let options = Options {
+        cycles: options.cycles.or(Some(6)),
+        ..options.clone()
+    };
+    animate::play(space, |s| s.slice(&slice), &options);
+    Ok(())
+}
+
+/// Draws a single slice through the extra axes after some cycles.
+pub fn picture(
+    input: bool,
+    part_b: bool,
+    cycles: usize,
+    slice: Option<Vec<i32>>,
+) -> Result<Picture, String> {
+    let (space, slice) = setup(input, part_b, slice)?;
+    Ok(Picture::new(&space.run(cycles).plane(&slice)))
+}
+
+/// Runs any rule in any number of dimensions, then draws a slice through
+/// the extra axes and counts the active cubes. Uses the example unless
+/// told otherwise.
+pub fn life(
+    input: bool,
+    rule: Rule,
+    dims: usize,
+    cycles: usize,
+    slice: Option<Vec<i32>>,
+) -> Result<String, String> {
+    if dims < 2 {
+        return Err("Conway spaces need at least two dimensions.".to_string());
+    }
+    let slice = slice.unwrap_or_else(|| vec![0; dims - 2]);
+    if slice.len() != dims - 2 {
+        return Err(format!("The slice needs {} coordinates.", dims - 2));
+    }
+    let data = if input {
+        ConwaySolution::from_file("conway.txt")
+    } else {
+        ConwaySolution::from_string(EXAMPLE)
+    };
+    let space = Space::from_plane(&data, dims).with_rule(rule);
+    let rule = space.rule.to_string();
+    let space = space.run_symmetric(cycles);
+    Ok(format!(
+        "{}{} active after {} cycles of {} in {} dimensions\n",
+        space.slice(&slice),
+        space.len(),
+        cycles,
+        rule,
+        dims
+    ))
+}
+
+/// Builds the space in 3 dimensions, or 4 for part b, along with the
+/// slice to draw. Uses the example unless told otherwise.
+fn setup(input: bool, part_b: bool, slice: Option<Vec<i32>>) -> Result<(Space, Vec<i32>), String> {
+    let dims = if part_b { 4 } else { 3 };
+    let slice = slice.unwrap_or_else(|| vec![0; dims - 2]);
+    if slice.len() != dims - 2 {
+        return Err(format!("The slice needs {} coordinates.", dims - 2));
     }
+    let data = if input {
+        ConwaySolution::from_file("conway.txt")
+    } else {
+        ConwaySolution::from_string(EXAMPLE)
+    };
+    Ok((Space::from_plane(&data, dims).with_rule(rule()), slice))
 }
 
 /// Determines whether a given character represents an active space.
@@ -53,111 +131,466 @@ fn is_active(c: char) -> bool {
     }
 }
 
-fn cycle(source: &Cube) -> Cube {
-    let mut new = get_cube(source.len());
-    for z in 1..(new.len() - 1) {
-        for y in 1..(new.len() - 1) {
-            for x in 1..(new.len() - 1) {
-                let mut count = count_surrounding(source, x, y, z);
-                let active = source[z][y][x];
-                if active {
-                    count -= 1;
-                }
-                if count == 3 || active && count == 2 {
-                    new[z][y][x] = true;
-                }
-            }
+/// The cubes which count as neighbors of any given cube.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// Every cube in the surrounding box, including diagonals.
+    Moore,
+    /// Every cube within a Manhattan distance of the radius.
+    VonNeumann,
+}
+
+/// A rule in B/S notation, e.g. `B3/S23`. Cubes are born with any of the
+/// `birth` counts of active neighbors and survive with any of the
+/// `survival` counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    birth: BTreeSet<u32>,
+    survival: BTreeSet<u32>,
+    neighborhood: Neighborhood,
+    radius: i32,
+}
+
+impl Rule {
+    /// Parses a rule such as `B3/S23` or `B36/S23`. Counts are single
+    /// digits unless separated by commas, which allows for ranges and
+    /// larger counts in higher dimensions, e.g. `B5-7/S4,10`. An optional
+    /// third part selects the neighborhood and radius, e.g. `V2` or `M1`.
+    /// Rules with `B0` are not supported, as an infinite number of cubes
+    /// would be born.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.trim().split('/');
+        let birth = parse_counts(parts.next()?.strip_prefix('B')?)?;
+        let survival = parse_counts(parts.next()?.strip_prefix('S')?)?;
+        let mut rule = Self::default();
+        if let Some(n) = parts.next() {
+            rule.neighborhood = match n.chars().next()? {
+                'M' => Neighborhood::Moore,
+                'V' => Neighborhood::VonNeumann,
+                _ => return None,
+            };
+            rule.radius = n[1..].parse().ok().filter(|&r| r > 0)?;
         }
+        if parts.next().is_some() || birth.contains(&0) {
+            return None;
+        }
+        rule.birth = birth;
+        rule.survival = survival;
+        Some(rule)
     }
-    new
-}
-
-fn cycle_4d(source: &Time) -> Time {
-    let mut new = get_time(source.len());
-    for t in 1..(new.len() - 1) {
-        for z in 1..(new.len() - 1) {
-            for y in 1..(new.len() - 1) {
-                for x in 1..(new.len() - 1) {
-                    let mut count = count_4d(source, x, y, z, t);
-                    let active = source[t][z][y][x];
-                    if active {
-                        count -= 1;
-                    }
-                    if count == 3 || active && count == 2 {
-                        new[t][z][y][x] = true;
-                    }
-                }
-            }
+
+    /// Replaces the neighborhood and its radius. Panics if the radius is
+    /// less than 1.
+    pub fn with_neighborhood(mut self, neighborhood: Neighborhood, radius: i32) -> Self {
+        assert!(radius > 0, "The radius must be at least 1.");
+        self.neighborhood = neighborhood;
+        self.radius = radius;
+        self
+    }
+
+    /// Determines whether a cube is active after the next cycle.
+    fn next(&self, active: bool, neighbors: u32) -> bool {
+        if active {
+            self.survival.contains(&neighbors)
+        } else {
+            self.birth.contains(&neighbors)
+        }
+    }
+
+    /// Generates the offset of every neighbor in `dims` dimensions.
+    fn offsets(&self, dims: usize) -> Vec<Point> {
+        let r = self.radius;
+        let mut offsets = vec![Vec::new()];
+        for _ in 0..dims {
+            offsets = offsets
+                .into_iter()
+                .flat_map(|o: Point| {
+                    (-r..=r).map(move |d| {
+                        let mut next = o.clone();
+                        next.push(d);
+                        next
+                    })
+                })
+                .collect();
         }
+        offsets.retain(|o| o.iter().any(|&d| d != 0));
+        if self.neighborhood == Neighborhood::VonNeumann {
+            offsets.retain(|o| o.iter().map(|d| d.abs()).sum::<i32>() <= r);
+        }
+        offsets
     }
-    new
 }
 
-fn count_total(cube: &Cube) -> u32 {
-    let mut count = 0;
-    for z in cube {
-        for y in z {
-            for &x in y {
-                if x {
-                    count += 1;
-                }
-            }
+/// The rule from the puzzle, which is also the rule for Conway's Game
+/// of Life: `B3/S23` with the Moore neighborhood.
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            birth: [3].iter().copied().collect(),
+            survival: [2, 3].iter().copied().collect(),
+            neighborhood: Neighborhood::Moore,
+            radius: 1,
         }
     }
-    count
 }
 
-fn count_total_4d(time: &Time) -> u32 {
-    let mut count = 0;
-    for cube in time {
-        count += count_total(cube);
+/// Writes the rule back in B/S notation. The neighborhood is only
+/// included when it is not the default.
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let short = self.birth.iter().chain(&self.survival).all(|&c| c < 10);
+        let join = |counts: &BTreeSet<u32>| {
+            let counts: Vec<String> = counts.iter().map(u32::to_string).collect();
+            counts.join(if short { "" } else { "," })
+        };
+        write!(f, "B{}/S{}", join(&self.birth), join(&self.survival))?;
+        match (self.neighborhood, self.radius) {
+            (Neighborhood::Moore, 1) => Ok(()),
+            (Neighborhood::Moore, r) => write!(f, "/M{}", r),
+            (Neighborhood::VonNeumann, r) => write!(f, "/V{}", r),
+        }
     }
-    count
 }
 
-fn get_cube(len: usize) -> Cube {
-    vec![vec![vec![false; len]; len]; len]
+/// Parses the counts in one half of a B/S rule.
+fn parse_counts(s: &str) -> Option<BTreeSet<u32>> {
+    if !s.contains(',') && !s.contains('-') {
+        return s.chars().map(|c| c.to_digit(10)).collect();
+    }
+    let mut counts = BTreeSet::new();
+    for part in s.split(',') {
+        let mut range = part.splitn(2, '-');
+        let lo: u32 = range.next()?.trim().parse().ok()?;
+        let hi: u32 = match range.next() {
+            Some(hi) => hi.trim().parse().ok()?,
+            None => lo,
+        };
+        counts.extend(lo..=hi);
+    }
+    Some(counts)
 }
 
-fn get_time(len: usize) -> Time {
-    vec![vec![vec![vec![false; len]; len]; len]; len]
+/// An infinite grid of cubes in any number of dimensions. Only the
+/// active cubes are stored, so the grid can grow in any direction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Space {
+    dims: usize,
+    active: HashSet<Point>,
+    rule: Rule,
+    /// The offset of every neighbor from any given cube.
+    offsets: Vec<Point>,
 }
 
-fn expand(cube: &Cube, len: usize) -> Cube {
-    let mut new = get_cube(len);
-    let offset_z = (len / 2) - (cube.len() / 2);
-    let offset_y = (len / 2) - (cube[0].len() / 2);
-    let offset_x = (len / 2) - (cube[0][0].len() / 2);
-    for (z, mat) in cube.iter().enumerate() {
-        for (y, vec) in mat.iter().enumerate() {
-            for (x, &b) in vec.iter().enumerate() {
-                new[z + offset_z][y + offset_y][x + offset_x] = b;
+impl Space {
+    /// Constructs a new space with every active cell in `plane` placed at
+    /// `(x, y, 0, 0, ...)`. There must be at least two dimensions.
+    pub fn from_plane(plane: &Grid<bool>, dims: usize) -> Self {
+        assert!(dims >= 2, "Conway spaces need at least two dimensions.");
+        let mut active = HashSet::new();
+        for ((x, y), &b) in plane.positions() {
+            if b {
+                let mut point = vec![0; dims];
+                point[0] = x as i32;
+                point[1] = y as i32;
+                active.insert(point);
+            }
+        }
+        let rule = Rule::default();
+        Self {
+            dims,
+            active,
+            offsets: rule.offsets(dims),
+            rule,
+        }
+    }
+
+    /// Replaces the rule used for every following cycle.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.offsets = rule.offsets(self.dims);
+        self.rule = rule;
+        self
+    }
+
+    /// The number of active cubes.
+    pub fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn is_active(&self, point: &[i32]) -> bool {
+        self.active.contains(point)
+    }
+
+    /// The plane where every axis beyond `x` and `y` matches `fixed`. The
+    /// plane covers every active cube in the space, so it stays the same
+    /// size when switching between slices.
+    pub fn plane(&self, fixed: &[i32]) -> Grid<bool> {
+        let xs = self.active.iter().map(|p| p[0]);
+        let ys = self.active.iter().map(|p| p[1]);
+        let (x0, x1) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(-1));
+        let (y0, y1) = (ys.clone().min().unwrap_or(0), ys.max().unwrap_or(-1));
+        let (width, height) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+        Grid::from_fn(width, height, |x, y| {
+            let mut point = vec![x0 + x as i32, y0 + y as i32];
+            point.extend(fixed);
+            self.is_active(&point)
+        })
+    }
+
+    /// Draws the plane where every axis beyond `x` and `y` matches
+    /// `fixed`, labeled with the position on those axes, if there are any.
+    pub fn slice(&self, fixed: &[i32]) -> String {
+        let labels = ["z", "w", "v", "u"];
+        let axes: Vec<String> = fixed
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{}={}", labels.get(i).unwrap_or(&"?"), c))
+            .collect();
+        let mut out = if axes.is_empty() {
+            String::new()
+        } else {
+            axes.join(", ") + "\n"
+        };
+        for row in self.plane(fixed).rows() {
+            out.extend(row.iter().map(|&b| if b { '#' } else { '.' }));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Runs the given number of cycles.
+    pub fn run(mut self, cycles: usize) -> Self {
+        for _ in 0..cycles {
+            self = self.cycle();
+        }
+        self
+    }
+
+    /// Applies the rule to every cube at once. By default, active cubes
+    /// stay active with 2 or 3 active neighbors and inactive cubes become
+    /// active with exactly 3.
+    pub fn cycle(&self) -> Self {
+        let mut counts: HashMap<Point, u32> = HashMap::new();
+        for point in &self.active {
+            // Isolated cubes are still needed for rules like `S0`.
+            counts.entry(point.clone()).or_insert(0);
+            for offset in &self.offsets {
+                let neighbor = point.iter().zip(offset).map(|(p, o)| p + o).collect();
+                *counts.entry(neighbor).or_insert(0) += 1;
             }
         }
+        let active = counts
+            .into_iter()
+            .filter(|(point, count)| self.rule.next(self.is_active(point), *count))
+            .map(|(point, _)| point)
+            .collect();
+        Self {
+            dims: self.dims,
+            active,
+            rule: self.rule.clone(),
+            offsets: self.offsets.clone(),
+        }
+    }
+
+    /// Counts the active cubes after the given number of cycles. This is
+    /// much faster when the space is symmetric in its extra axes.
+    pub fn count_after(self, cycles: usize) -> usize {
+        match self.fold() {
+            Some(folded) => folded.run(cycles).len(),
+            None => self.run(cycles).len(),
+        }
+    }
+
+    /// Runs the given number of cycles, only storing one cube from each
+    /// set of mirror images if the space is symmetric.
+    pub fn run_symmetric(self, cycles: usize) -> Self {
+        match self.fold() {
+            Some(folded) => folded.run(cycles).unfold(),
+            None => self.run(cycles),
+        }
+    }
+
+    /// Determines whether this space looks the same after mirroring or
+    /// swapping any of the axes beyond `x` and `y`. Every rule preserves
+    /// this, so it stays true forever.
+    pub fn is_symmetric(&self) -> bool {
+        self.active.iter().all(|point| {
+            let mut image = point.clone();
+            (2..self.dims).all(|i| {
+                image[i] = -image[i];
+                let mirrored = self.is_active(&image);
+                image[i] = -image[i];
+                let swapped = i + 1 == self.dims || {
+                    image.swap(i, i + 1);
+                    let swapped = self.is_active(&image);
+                    image.swap(i, i + 1);
+                    swapped
+                };
+                mirrored && swapped
+            })
+        })
+    }
+
+    /// Stores only one cube from each set of symmetric cubes, if the
+    /// space is symmetric.
+    pub fn fold(&self) -> Option<Folded> {
+        if !self.is_symmetric() {
+            return None;
+        }
+        let active = self.active.iter().cloned().map(canonical).collect();
+        Some(Folded {
+            space: Space {
+                active,
+                ..self.clone()
+            },
+        })
     }
-    new
 }
 
-fn count_4d(source: &Time, x: usize, y: usize, z: usize, t: usize) -> u32 {
-    let mut count = 0;
-    for cube in &source[(t - 1)..(t + 2)] {
-        count += count_surrounding(cube, x, y, z);
+/// Spaces are keyed by their active cubes relative to the lowest corner
+/// of their bounding box.
+impl Automaton for Space {
+    type Key = Vec<Point>;
+
+    fn step(&mut self) {
+        *self = self.cycle();
+    }
+
+    fn key(&self) -> (Vec<Point>, Vec<i32>) {
+        let mut corner = vec![0; self.dims];
+        for (i, c) in corner.iter_mut().enumerate() {
+            *c = self.active.iter().map(|p| p[i]).min().unwrap_or(0);
+        }
+        let mut key: Vec<Point> = self
+            .active
+            .iter()
+            .map(|p| p.iter().zip(&corner).map(|(p, c)| p - c).collect())
+            .collect();
+        key.sort_unstable();
+        (key, corner)
     }
-    count
 }
 
-fn count_surrounding(source: &Cube, x: usize, y: usize, z: usize) -> u32 {
-    let mut count = 0;
-    for mat in &source[(z - 1)..(z + 2)] {
-        for vec in &mat[(y - 1)..(y + 2)] {
-            for &b in &vec[(x - 1)..(x + 2)] {
-                if b {
-                    count += 1;
-                }
+/// A symmetric space which only stores the cubes whose extra axes are
+/// non-negative and sorted. Every other cube is a mirror image of one of
+/// these, so each stored cube stands in for its `multiplicity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folded {
+    space: Space,
+}
+
+impl Folded {
+    /// The number of active cubes, including every mirror image.
+    pub fn len(&self) -> usize {
+        self.space.active.iter().map(|p| multiplicity(p)).sum()
+    }
+
+    pub fn run(mut self, cycles: usize) -> Self {
+        for _ in 0..cycles {
+            self = self.cycle();
+        }
+        self
+    }
+
+    /// Applies the rule to every stored cube. For each pair of stored
+    /// cubes `s` and `t`, the number of images of `s` next to `t` is the
+    /// number of images of `t` next to `s`, scaled by the ratio of their
+    /// multiplicities. Counts are kept scaled until the end so that they
+    /// stay whole numbers.
+    pub fn cycle(&self) -> Self {
+        let space = &self.space;
+        let mut counts: HashMap<Point, usize> = HashMap::new();
+        for point in &space.active {
+            counts.entry(point.clone()).or_insert(0);
+            let weight = multiplicity(point);
+            for offset in &space.offsets {
+                let neighbor = point.iter().zip(offset).map(|(p, o)| p + o).collect();
+                *counts.entry(canonical(neighbor)).or_insert(0) += weight;
+            }
+        }
+        let active = counts
+            .into_iter()
+            .filter(|(point, count)| {
+                let neighbors = count / multiplicity(point);
+                space.rule.next(space.is_active(point), neighbors as u32)
+            })
+            .map(|(point, _)| point)
+            .collect();
+        Self {
+            space: Space {
+                active,
+                ..space.clone()
+            },
+        }
+    }
+
+    /// Restores every mirror image of the stored cubes.
+    pub fn unfold(&self) -> Space {
+        let mut active = HashSet::new();
+        for point in &self.space.active {
+            let mut images = vec![point.clone()];
+            for i in 2..point.len() {
+                let mirrored: Vec<Point> = images
+                    .iter()
+                    .filter(|p| p[i] != 0)
+                    .map(|p| {
+                        let mut p = p.clone();
+                        p[i] = -p[i];
+                        p
+                    })
+                    .collect();
+                images.extend(mirrored);
             }
+            for image in images {
+                permute(image, 2, &mut active);
+            }
+        }
+        Space {
+            active,
+            ..self.space.clone()
         }
     }
-    count
+}
+
+/// Mirrors and sorts the extra axes of a point so that every image of
+/// it looks the same.
+fn canonical(mut point: Point) -> Point {
+    for d in &mut point[2..] {
+        *d = d.abs();
+    }
+    point[2..].sort_unstable();
+    point
+}
+
+/// The number of distinct images of a canonical point: 2 for every
+/// non-zero extra axis, times the number of distinct orderings.
+fn multiplicity(point: &[i32]) -> usize {
+    let extra = &point[2..];
+    let mirrors = 1 << extra.iter().filter(|&&d| d != 0).count();
+    let mut orderings = factorial(extra.len());
+    let mut i = 0;
+    while i < extra.len() {
+        let run = extra[i..].iter().take_while(|&&d| d == extra[i]).count();
+        orderings /= factorial(run);
+        i += run;
+    }
+    mirrors * orderings
+}
+
+fn factorial(n: usize) -> usize {
+    (1..=n).product()
+}
+
+/// Inserts every distinct ordering of the axes after `from`.
+fn permute(mut point: Point, from: usize, out: &mut HashSet<Point>) {
+    if from >= point.len() {
+        out.insert(point);
+        return;
+    }
+    for i in from..point.len() {
+        point.swap(from, i);
+        permute(point.clone(), from + 1, out);
+        point.swap(from, i);
+    }
 }
 
 #[test]
@@ -173,3 +606,149 @@ fn test_solution_b() {
     let data = ConwaySolution::from_string(example);
     assert_eq!(ConwaySolution::get_solution_b(&data).unwrap(), 848)
 }
+
+#[test]
+fn test_dimensions() {
+    // In 2D, this is a glider. It moves one cell diagonally every 4 cycles.
+    let data = ConwaySolution::from_string(".#.\n..#\n###");
+    let glider = Space::from_plane(&data, 2);
+    let moved = glider.clone().run(4);
+    assert_eq!(moved.len(), 5);
+    assert!(glider
+        .active
+        .iter()
+        .all(|p| moved.is_active(&[p[0] + 1, p[1] + 1])));
+
+    // z=-1 has 3 active cubes, z=0 has 5 and z=1 has 3.
+    assert_eq!(Space::from_plane(&data, 3).cycle().len(), 11);
+    assert_eq!(Space::from_plane(&data, 5).offsets.len(), 242);
+}
+
+#[test]
+fn test_rules() {
+    for notation in &[
+        "B3/S23",
+        "B36/S23",
+        "B1/S012345678",
+        "B5,10/S4,5,6/V2",
+        "B2/S/M3",
+    ] {
+        assert_eq!(Rule::parse(notation).unwrap().to_string(), *notation);
+    }
+    assert_eq!(Rule::parse(RULE), Some(Rule::default()));
+    assert_eq!(Rule::parse("B3/S23/M1"), Some(Rule::default()));
+    assert_eq!(Rule::parse("B3/S2-3"), Some(Rule::default()));
+    assert_eq!(Rule::parse("B03/S23"), None);
+    assert_eq!(Rule::parse("S23/B3"), None);
+    assert_eq!(Rule::parse("B3/S23/X1"), None);
+
+    let von_neumann = Rule::default().with_neighborhood(Neighborhood::VonNeumann, 1);
+    assert_eq!(von_neumann.offsets(3).len(), 6);
+    assert_eq!(
+        von_neumann
+            .with_neighborhood(Neighborhood::VonNeumann, 2)
+            .offsets(2)
+            .len(),
+        12
+    );
+    assert_eq!(Rule::parse("B3/S23/M2").unwrap().offsets(2).len(), 24);
+
+    // A blinker flips between horizontal and vertical in classic Life.
+    let blinker = Space::from_plane(&ConwaySolution::from_string("###"), 2);
+    assert!(blinker.cycle().is_active(&[1, -1]));
+    assert_eq!(blinker.clone().run(2), blinker);
+
+    // `S0` keeps isolated cubes alive, which `S23` does not.
+    let lonely = Space::from_plane(&ConwaySolution::from_string("#"), 3);
+    assert_eq!(lonely.cycle().len(), 0);
+    let lonely = lonely.with_rule(Rule::parse("B9/S0").unwrap());
+    assert_eq!(lonely.cycle().len(), 1);
+}
+
+#[test]
+fn test_symmetry() {
+    let data = ConwaySolution::from_string(".#.\n..#\n###");
+    assert_eq!(multiplicity(&[0, 0, 0, 0]), 1);
+    assert_eq!(multiplicity(&[0, 0, 0, 1, 1]), 12);
+    assert_eq!(multiplicity(&[0, 0, 1, 2, 3]), 48);
+
+    for dims in 3..=5 {
+        let space = Space::from_plane(&data, dims);
+        let folded = space.fold().unwrap();
+        for cycles in 0..3 {
+            let expected = space.clone().run(cycles);
+            let actual = folded.clone().run(cycles);
+            assert_eq!(actual.len(), expected.len());
+            assert_eq!(actual.unfold(), expected);
+        }
+    }
+    assert_eq!(Space::from_plane(&data, 4).count_after(6), 848);
+    let space = Space::from_plane(&data, 4);
+    assert_eq!(space.clone().run_symmetric(2), space.run(2));
+
+    // Moving the plane off of z = 0 breaks the symmetry.
+    let mut shifted = Space::from_plane(&data, 3);
+    shifted.active = shifted
+        .active
+        .into_iter()
+        .map(|p| vec![p[0], p[1], 1])
+        .collect();
+    assert!(shifted.fold().is_none());
+    assert_eq!(shifted.count_after(6), 112);
+}
+
+#[test]
+fn test_run() {
+    use crate::automaton::{self, Outcome};
+    let space = |s: &str| Space::from_plane(&ConwaySolution::from_string(s), 2);
+
+    let glider = automaton::run(space(".#.\n..#\n###"), 100);
+    let displacement = vec![1, 1];
+    assert_eq!(
+        glider.outcome,
+        Outcome::Spaceship {
+            period: 4,
+            displacement
+        }
+    );
+    assert_eq!((glider.start, glider.steps), (Some(0), 4));
+
+    let blinker = automaton::run(space("###"), 100);
+    assert_eq!(blinker.outcome, Outcome::Oscillator { period: 2 });
+
+    // This one takes a step to settle into a block.
+    let block = automaton::run(space("##\n#."), 100);
+    assert_eq!(block.outcome, Outcome::Stable);
+    assert_eq!((block.start, block.last.len()), (Some(1), 4));
+
+    let dead = automaton::run(space("#"), 100);
+    assert_eq!((dead.outcome, dead.last.len()), (Outcome::Stable, 0));
+}
+
+#[test]
+fn test_slice() {
+    let space = Space::from_plane(&ConwaySolution::from_string(EXAMPLE), 3).cycle();
+    assert_eq!(space.slice(&[-1]), "z=-1\n#..\n..#\n.#.\n");
+    assert_eq!(space.slice(&[0]), "z=0\n#.#\n.##\n.#.\n");
+    assert_eq!(space.slice(&[5]), "z=5\n...\n...\n...\n");
+
+    let plane = space.plane(&[-1]);
+    assert_eq!((plane.width(), plane.height()), (3, 3));
+    let pbm = Picture::new(&plane).to_pbm();
+    assert_eq!(pbm, "P1\n3 3\n1 0 0\n0 0 1\n0 1 0\n");
+    let pbm = picture(false, true, 0, Some(vec![0, 0])).unwrap().to_pbm();
+    assert_eq!(pbm, "P1\n3 3\n0 1 0\n0 0 1\n1 1 1\n");
+    assert!(picture(false, true, 0, Some(vec![0])).is_err());
+
+    let out = life(false, rule(), 3, 1, Some(vec![-1])).unwrap();
+    assert_eq!(
+        out,
+        "z=-1\n#..\n..#\n.#.\n11 active after 1 cycles of B3/S23 in 3 dimensions\n"
+    );
+    let von_neumann = rule().with_neighborhood(Neighborhood::VonNeumann, 2);
+    let out = life(false, von_neumann, 2, 0, None).unwrap();
+    assert!(out.starts_with(".#.\n"));
+    assert!(out.ends_with("5 active after 0 cycles of B3/S23/V2 in 2 dimensions\n"));
+    assert!(life(false, rule(), 1, 0, None).is_err());
+    assert!(life(false, rule(), 4, 0, Some(vec![0])).is_err());
+}
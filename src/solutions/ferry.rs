@@ -1,169 +1,191 @@
+use crate::animate::{self, Options};
+use crate::automaton::{self, GridAutomaton, Neighborhood, Transition};
+use crate::export::{Picture, Pixel};
+use crate::grid::Grid;
 use crate::solution_template::Solution;
 use crate::solutions::ferry::SeatStatus::*;
 use std::fmt::{Debug, Formatter};
+use std::time::Instant;
 
 pub struct FerrySolution;
 
 /// People are taking seats on a ferry. They take seats on predictable increments
 /// in predictable places based on a set of rules.
 impl Solution for FerrySolution {
-    type Data = Vec<Vec<SeatStatus>>;
+    type Data = Grid<SeatStatus>;
     type Output = i32;
 
     const MESSAGE_A: &'static str = "Final number occupied (surrounding)";
     const MESSAGE_B: &'static str = "Final number occupied (direction)";
 
-    fn from_string(s: &str) -> Vec<Vec<SeatStatus>> {
+    fn from_string(s: &str) -> Grid<SeatStatus> {
         Self::map_chars(s, |c| SeatStatus::parse(c).expect(&c.to_string()))
     }
 
     /// Figure out how many seats are occupied when no more seats can be taken.
-    fn get_solution_a(data: &Vec<Vec<SeatStatus>>) -> Option<i32> {
-        // Using clones so we don't tamper with the conditions.
-        let mut matrix = data.clone();
-        loop {
-            let (m, count) = update_once(&matrix);
-            matrix = m;
-            if count == 0 {
-                break;
-            }
-        }
-        Some(total_occupied(&matrix))
+    fn get_solution_a(data: &Grid<SeatStatus>) -> Option<i32> {
+        let mut ferry = adjacent(data);
+        ferry.settle();
+        Some(ferry.count(|&s| s == Occupied) as i32)
     }
 
     /// Check in the surrounding *directions* for occupied seats.
-    fn get_solution_b(data: &Vec<Vec<SeatStatus>>) -> Option<i32> {
-        let mut matrix = data.clone();
-        loop {
-            let (m, count) = update_once_direction(&matrix);
-            matrix = m;
-            if count == 0 {
-                break;
-            }
-        }
-        Some(total_occupied(&matrix))
-    }
-}
-
-fn update_once(matrix: &Vec<Vec<SeatStatus>>) -> (Vec<Vec<SeatStatus>>, i32) {
-    let mut clone = matrix.clone();
-    let mut num_changed = 0;
-    for y in 0..matrix.len() {
-        for x in 0..matrix[y].len() {
-            let occupied = check_surrounding(&matrix, x, y);
-            let seat = &matrix[y][x];
-            if *seat == Vacant && occupied == 0 {
-                clone[y][x] = Occupied;
-                num_changed += 1;
-            } else if *seat == Occupied && occupied >= 4 {
-                clone[y][x] = Vacant;
-                num_changed += 1;
-            }
-        }
+    fn get_solution_b(data: &Grid<SeatStatus>) -> Option<i32> {
+        let mut ferry = line_of_sight(data);
+        ferry.settle();
+        Some(ferry.count(|&s| s == Occupied) as i32)
     }
-    (clone, num_changed)
-}
-
-fn check_surrounding(matrix: &Vec<Vec<SeatStatus>>, x: usize, y: usize) -> i32 {
-    // Avoid subtracting with overflow.
-    let x = x as isize;
-    let y = y as isize;
-    let positions = [
-        (x + 1, y + 1),
-        (x + 1, y),
-        (x, y + 1),
-        (x - 1, y - 1),
-        (x - 1, y),
-        (x, y - 1),
-        (x + 1, y - 1),
-        (x - 1, y + 1),
-    ];
-    let mut occupied = 0;
-    for &(x, y) in &positions {
-        if x >= 0 && y >= 0 {
-            occupied += check(matrix, x as usize, y as usize);
-        }
+}
+
+/// People only care about the seats right next to them. They leave when
+/// 4 or more of those are occupied.
+fn adjacent(data: &Grid<SeatStatus>) -> GridAutomaton<SeatStatus> {
+    GridAutomaton::new(data, Neighborhood::Adjacent, |s, n| s.next(n, 4))
+}
+
+/// People care about the first seat they can see in every direction.
+/// They leave when 5 or more of those are occupied.
+fn line_of_sight(data: &Grid<SeatStatus>) -> GridAutomaton<SeatStatus> {
+    let floor = Neighborhood::LineOfSight(|&s| s == Floor);
+    GridAutomaton::new(data, floor, |s, n| s.next(n, 5))
+}
+
+/// Animates the seats filling up, using the example unless told otherwise.
+pub fn animate(options: &Options) -> Result<(), String> {
+    if options.slice.is_some() {
+        return Err("Ferries only have two dimensions.".to_string());
     }
-    occupied
+    animate::play(setup(options.input, options.part_b), render, options);
+    Ok(())
+}
+
+/// Draws the seats once nobody moves anymore.
+pub fn picture(input: bool, part_b: bool) -> Picture {
+    let mut ferry = setup(input, part_b);
+    ferry.settle();
+    Picture::new(ferry.grid())
 }
 
-fn check(matrix: &Vec<Vec<SeatStatus>>, x: usize, y: usize) -> i32 {
-    if let Some(vec) = matrix.get(y) {
-        if let Some(Occupied) = vec.get(x) {
-            return 1;
+/// Runs the seats until they repeat, using the tolerance from either
+/// part and any neighborhood, then describes how that went. Also lists
+/// what a single seat counts as its neighbors, if asked.
+pub fn classify(
+    input: bool,
+    part_b: bool,
+    neighbors: Option<&str>,
+    limit: usize,
+    seat: Option<(usize, usize)>,
+) -> Result<String, String> {
+    let data = load(input);
+    let neighborhood = match neighbors {
+        Some(s) => parse_neighborhood(s).ok_or(format!("Invalid neighborhood: {}", s))?,
+        None if part_b => Neighborhood::LineOfSight(|&s| s == Floor),
+        None => Neighborhood::Adjacent,
+    };
+    let transition: Transition<SeatStatus> = if part_b {
+        |s, n| s.next(n, 5)
+    } else {
+        |s, n| s.next(n, 4)
+    };
+    let ferry = GridAutomaton::new(&data, neighborhood, transition);
+    let mut out = String::new();
+    if let Some((x, y)) = seat {
+        if data.get(x, y).is_none() {
+            return Err(format!("There is no seat at {:?}.", (x, y)));
         }
+        let mut neighbors = Vec::new();
+        ferry.neighbors(x, y, &mut neighbors);
+        out += &format!("Seat {:?} has neighbors {:?}\n", (x, y), neighbors);
     }
-    0
-}
-
-fn update_once_direction(matrix: &Vec<Vec<SeatStatus>>) -> (Vec<Vec<SeatStatus>>, i32) {
-    let mut clone = matrix.clone();
-    let mut num_changed = 0;
-    for y in 0..matrix.len() {
-        for x in 0..matrix[y].len() {
-            let occupied = check_surrounding_directions(&matrix, x, y);
-            let seat = &matrix[y][x];
-            if *seat == Vacant && occupied == 0 {
-                clone[y][x] = Occupied;
-                num_changed += 1;
-            } else if *seat == Occupied && occupied >= 5 {
-                clone[y][x] = Vacant;
-                num_changed += 1;
-            }
-        }
+    let summary = automaton::run(ferry, limit);
+    out += &render(&summary.last);
+    if let Some(start) = summary.start {
+        out += &format!("{} from step {}. ", summary.outcome, start);
+    } else {
+        out += &format!("{} after {} steps. ", summary.outcome, summary.steps);
     }
-    (clone, num_changed)
-}
-
-fn check_surrounding_directions(matrix: &Vec<Vec<SeatStatus>>, x: usize, y: usize) -> i32 {
-    // Avoid subtracting with overflow.
-    let slopes = [
-        (1, 1),
-        (1, 0),
-        (0, 1),
-        (-1, -1),
-        (-1, 0),
-        (0, -1),
-        (1, -1),
-        (-1, 1),
-    ];
-    let mut occupied = 0;
-    for &(sx, sy) in &slopes {
-        occupied += check_direction(matrix, (x, y), (sx, sy));
-    }
-    occupied
-}
-
-fn check_direction(matrix: &Vec<Vec<SeatStatus>>, i: (usize, usize), s: (isize, isize)) -> i32 {
-    let mut x = i.0 as isize + s.0;
-    let mut y = i.1 as isize + s.1;
-    while x >= 0 && x < matrix[0].len() as isize && y >= 0 && y < matrix.len() as isize {
-        match matrix[y as usize][x as usize] {
-            Floor => {
-                x += s.0;
-                y += s.1;
-                continue;
-            }
-            Occupied => return 1,
-            Vacant => return 0,
+    let occupied = summary.last.count(|&s| s == Occupied);
+    out += &format!("{} occupied.\n", occupied);
+    Ok(out)
+}
+
+/// Parses `adjacent`, `orthogonal`, `sight` or a list of offsets such
+/// as `1,2;-1,2`.
+fn parse_neighborhood(s: &str) -> Option<Neighborhood<SeatStatus>> {
+    match s {
+        "adjacent" => Some(Neighborhood::Adjacent),
+        "orthogonal" => Some(Neighborhood::Orthogonal),
+        "sight" => Some(Neighborhood::LineOfSight(|&s| s == Floor)),
+        _ => {
+            let offsets = s.split(';').map(|offset| {
+                let mut split = offset.split(',');
+                let dx = split.next()?.trim().parse().ok()?;
+                let dy = split.next()?.trim().parse().ok()?;
+                Some((dx, dy)).filter(|_| split.next().is_none())
+            });
+            offsets.collect::<Option<_>>().map(Neighborhood::Offsets)
         }
     }
-    0
 }
 
-fn total_occupied(matrix: &Vec<Vec<SeatStatus>>) -> i32 {
-    let mut total = 0;
-    for vec in matrix {
-        for seat in vec {
-            if let Occupied = *seat {
-                total += 1;
-            }
+/// Lays out the seats for either part, using the example unless told
+/// otherwise.
+fn setup(input: bool, part_b: bool) -> GridAutomaton<SeatStatus> {
+    let data = load(input);
+    if part_b {
+        line_of_sight(&data)
+    } else {
+        adjacent(&data)
+    }
+}
+
+fn load(input: bool) -> Grid<SeatStatus> {
+    if input {
+        FerrySolution::from_file("ferry.txt")
+    } else {
+        get_example()
+    }
+}
+
+/// Times building each layout and settling it with full and incremental
+/// steps on the puzzle input, failing if the two kinds of steps disagree.
+/// Run with `cargo run --release -- --bench ferry`.
+pub fn bench() -> Result<String, String> {
+    let data = FerrySolution::from_file("ferry.txt");
+    let mut out = String::new();
+    for (name, build) in &[
+        ("adjacent", adjacent as fn(&_) -> _),
+        ("line of sight", line_of_sight),
+    ] {
+        let start = Instant::now();
+        let ferry = build(&data);
+        out.push_str(&format!("{}: layout in {:?}\n", name, start.elapsed()));
+
+        let (mut full, start) = (ferry.clone(), Instant::now());
+        while full.step_in_place() > 0 {}
+        out.push_str(&format!("{}: full steps in {:?}\n", name, start.elapsed()));
+
+        let (mut incremental, start) = (ferry, Instant::now());
+        incremental.settle();
+        let elapsed = start.elapsed();
+        out.push_str(&format!("{}: incremental steps in {:?}\n", name, elapsed));
+        if full.grid() != incremental.grid() {
+            return Err(format!("{}: incremental steps settled differently.", name));
         }
     }
-    total
+    Ok(out)
+}
+
+fn render(ferry: &GridAutomaton<SeatStatus>) -> String {
+    let mut out = String::new();
+    for row in ferry.grid().rows() {
+        row.iter().for_each(|s| out.push_str(&format!("{:?}", s)));
+        out.push('\n');
+    }
+    out
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 pub enum SeatStatus {
     Occupied,
     Vacant,
@@ -171,6 +193,17 @@ pub enum SeatStatus {
 }
 
 impl SeatStatus {
+    /// People take any seat with no occupied neighbors and leave any seat
+    /// with at least `tolerance`.
+    fn next(self, neighbors: &[SeatStatus], tolerance: usize) -> SeatStatus {
+        let occupied = neighbors.iter().filter(|&&s| s == Occupied).count();
+        match self {
+            Vacant if occupied == 0 => Occupied,
+            Occupied if occupied >= tolerance => Vacant,
+            s => s,
+        }
+    }
+
     fn parse(c: char) -> Option<SeatStatus> {
         match c {
             'L' => Some(Vacant),
@@ -191,6 +224,17 @@ impl Debug for SeatStatus {
     }
 }
 
+/// Occupied seats are dark, vacant seats are light and the floor is white.
+impl Pixel for SeatStatus {
+    fn color(&self) -> [u8; 3] {
+        match self {
+            Occupied => [40, 40, 160],
+            Vacant => [170, 200, 240],
+            Floor => [255, 255, 255],
+        }
+    }
+}
+
 #[test]
 fn test_solution_a() {
     assert_eq!(FerrySolution::get_solution_a(&get_example()).unwrap(), 37)
@@ -201,6 +245,26 @@ fn test_solution_b() {
     assert_eq!(FerrySolution::get_solution_b(&get_example()).unwrap(), 26)
 }
 
+#[test]
+fn test_run() {
+    use crate::automaton;
+    let summary = automaton::run(adjacent(&get_example()), usize::MAX);
+    assert_eq!(summary.outcome, automaton::Outcome::Stable);
+    assert_eq!((summary.start, summary.steps), (Some(5), 6));
+    assert_eq!(render(&summary.last).lines().next(), Some("#.#L.L#.##"));
+
+    let out = classify(false, true, None, 100, Some((0, 0))).unwrap();
+    assert!(out.starts_with("Seat (0, 0) has neighbors [L, L, L]\n"));
+    assert!(out.ends_with("stable from step 6. 26 occupied.\n"));
+    // Nobody ever leaves when only two seats count as neighbors.
+    let out = classify(false, false, Some("1,0;-1,0"), 100, None).unwrap();
+    assert!(out.ends_with("stable from step 1. 71 occupied.\n"));
+    let out = classify(false, false, Some("orthogonal"), 1, None).unwrap();
+    assert!(out.ends_with("unresolved after 1 steps. 71 occupied.\n"));
+    assert!(classify(false, false, Some("1,2,3"), 100, None).is_err());
+    assert!(classify(false, false, None, 100, Some((10, 0))).is_err());
+}
+
 #[test]
 fn test_update_direction() {
     let example = ".......#.
@@ -214,12 +278,36 @@ fn test_update_direction() {
         ...#.....";
     let example = example.replace(" ", "");
     let data = FerrySolution::from_string(&example);
-    let surrounding = check_surrounding_directions(&data, 3, 4);
-    assert_eq!(surrounding, 8);
+    let mut neighbors = Vec::new();
+    line_of_sight(&data).neighbors(3, 4, &mut neighbors);
+    assert_eq!(neighbors.iter().filter(|&&s| s == Occupied).count(), 8);
+}
+
+#[test]
+fn test_picture() {
+    let ppm = picture(false, false).to_ppm();
+    let first = ppm.lines().nth(3).unwrap();
+    assert!(first.starts_with("40 40 160 255 255 255 40 40 160 170 200 240"));
+}
+
+#[test]
+fn test_incremental() {
+    for build in &[adjacent as fn(&_) -> _, line_of_sight] {
+        let mut full = build(&get_example());
+        let mut incremental = full.clone();
+        loop {
+            let changed = full.step_in_place();
+            assert_eq!(changed, incremental.step_incremental());
+            assert_eq!(full.grid(), incremental.grid());
+            if changed == 0 {
+                break;
+            }
+        }
+    }
 }
 
-#[cfg(test)]
-fn get_example() -> Vec<Vec<SeatStatus>> {
+/// The example from the puzzle, which doubles as the animation demo.
+fn get_example() -> Grid<SeatStatus> {
     let example = "L.LL.LL.LL
         LLLLLLL.LL
         L.L.L..L..
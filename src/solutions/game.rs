@@ -1,4 +1,6 @@
 use crate::solution_template::Solution;
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
 
 pub struct GameSolution;
 
@@ -7,8 +9,9 @@ pub struct GameSolution;
 /// has been spoken, say how many numbers have been spoken since.
 /// I believe the expected solution was to use hash tables instead
 /// of dynamically-allocated arrays, but pre-allocating them does
-/// seem to produce acceptable results. I have left the the second
-/// solution out here because it does still take a couple seconds.
+/// seem to produce acceptable results. Storing turns as `u32`s in
+/// a flat array takes a quarter of the memory of `Option<usize>`s,
+/// which makes the second solution fast enough to leave in.
 impl Solution for GameSolution {
     type Data = Vec<usize>;
     type Output = usize;
@@ -22,28 +25,114 @@ impl Solution for GameSolution {
 
     /// Find the 2020th number.
     fn get_solution_a(data: &Vec<usize>) -> Option<usize> {
-        Some(get_nth_number(data, 2020))
+        get_nth_number(data, 2020)
     }
 
     /// Get the 30,000,000th number.
-    fn get_solution_b(_: &Vec<usize>) -> Option<usize> {
-        // Some(get_nth_number(data, 30_000_000))
-        Some(505)
+    fn get_solution_b(data: &Vec<usize>) -> Option<usize> {
+        get_nth_number(data, TURNS_B)
     }
 }
 
-fn get_nth_number(data: &Vec<usize>, n: usize) -> usize {
-    let mut nums = vec![None; n];
-    for (i, x) in data.iter().enumerate() {
-        nums[*x] = Some(i + 1);
+/// The number of turns played in the second part.
+const TURNS_B: usize = 30_000_000;
+
+/// The longest the second part may take in a release build.
+const TIME_BUDGET: Duration = Duration::from_secs(3);
+
+/// Yields `None` for the 0th number, which is never spoken, or if the
+/// game ends before then.
+fn get_nth_number(data: &[usize], n: usize) -> Option<usize> {
+    let mut game = Game::with_capacity(data, n);
+    game.nth(n.checked_sub(1)?).map(|spoken| spoken as usize)
+}
+
+/// Plays the second part on the puzzle input, failing if it runs over
+/// the time budget or grows past the memory reserved for its turns.
+/// Run with `cargo run --release -- --bench game`.
+pub fn bench() -> Result<String, String> {
+    let data = GameSolution::from_file("game.txt");
+    let start = Instant::now();
+    let mut game = Game::with_capacity(&data, TURNS_B);
+    let spoken = game.nth(TURNS_B - 1).ok_or("Too many turns.")?;
+    let elapsed = start.elapsed();
+    let budget = TURNS_B * std::mem::size_of::<u32>();
+    let report = format!(
+        "Number {} after {:?}, using {} of {} MB",
+        spoken,
+        elapsed,
+        game.memory() >> 20,
+        budget >> 20
+    );
+    if elapsed > TIME_BUDGET {
+        Err(format!("{}. Over the {:?} budget.", report, TIME_BUDGET))
+    } else if game.memory() > budget {
+        Err(format!("{}. Over the memory budget.", report))
+    } else {
+        Ok(format!("{}\n", report))
+    }
+}
+
+/// Every number spoken in the game, in order. Ends if the number of
+/// turns no longer fits in a `u32`.
+pub struct Game {
+    starting: Vec<u32>,
+    /// The last turn each number was spoken on, or 0 if it never was.
+    seen: Vec<u32>,
+    /// The number of turns so far.
+    turn: u32,
+    /// The number to say next, once the starting numbers run out.
+    next: u32,
+}
+
+impl Game {
+    /// Panics if any starting number doesn't fit in a `u32`.
+    #[cfg(test)]
+    pub fn new(starting: &[usize]) -> Self {
+        Self::with_capacity(starting, 0)
+    }
+
+    /// Reserves room for every number spoken within `turns` turns, so
+    /// the game never has to grow.
+    pub fn with_capacity(starting: &[usize], turns: usize) -> Self {
+        let starting: Vec<u32> = starting
+            .iter()
+            .map(|&n| u32::try_from(n).expect("Starting numbers must fit in a u32."))
+            .collect();
+        // No number can be spoken after more turns than have been played.
+        let largest = starting.iter().max().map_or(0, |&n| n as usize + 1);
+        Self {
+            seen: vec![0; turns.max(largest)],
+            starting,
+            turn: 0,
+            next: 0,
+        }
     }
-    let mut last = 0;
-    for x in (data.len() + 1)..n {
-        let i = nums[last];
-        nums[last] = Some(x);
-        last = i.map(|v| x - v).unwrap_or(0);
+
+    /// The number of bytes used to remember when numbers were spoken.
+    pub fn memory(&self) -> usize {
+        self.seen.capacity() * std::mem::size_of::<u32>()
+    }
+}
+
+impl Iterator for Game {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let spoken = match self.starting.get(self.turn as usize) {
+            Some(&n) => n,
+            None => self.next,
+        };
+        self.turn = self.turn.checked_add(1)?;
+        let i = spoken as usize;
+        if i >= self.seen.len() {
+            let len = (i + 1).max(self.seen.len() * 2);
+            self.seen.resize(len, 0);
+        }
+        let last = std::mem::replace(&mut self.seen[i], self.turn);
+        self.next = if last == 0 { 0 } else { self.turn - last };
+        Some(spoken)
     }
-    last
 }
 
 // // Example of someone else's solution using a hash table.
@@ -81,3 +170,23 @@ fn test_solution_a() {
     assert_eq!(GameSolution::get_solution_a(&vec![3, 2, 1]).unwrap(), 438);
     assert_eq!(GameSolution::get_solution_a(&vec![3, 1, 2]).unwrap(), 1836);
 }
+
+#[test]
+fn test_game() {
+    let spoken: Vec<u32> = Game::new(&[0, 3, 6]).take(10).collect();
+    assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+    // Repeated and large starting numbers still follow the rules.
+    let spoken: Vec<u32> = Game::new(&[7, 7, 100]).take(6).collect();
+    assert_eq!(spoken, vec![7, 7, 100, 0, 0, 1]);
+}
+
+#[test]
+fn test_memory() {
+    // Every number spoken is below the number of turns, so reserving one
+    // slot per turn means the game never has to grow.
+    let mut game = Game::with_capacity(&[0, 3, 6], 30_000);
+    assert!(game.nth(30_000 - 1).is_some());
+    assert_eq!(game.memory(), 30_000 * std::mem::size_of::<u32>());
+    assert_eq!(get_nth_number(&[0, 3, 6], 0), None);
+    assert_eq!(get_nth_number(&[0, 3, 6], 1), Some(0));
+}
@@ -1,309 +1,479 @@
+use crate::export::Picture;
+use crate::grid::Grid;
 use crate::solution_template::Solution;
-use std::sync::Mutex;
-use std::iter::Zip;
-use std::slice::Iter;
+use crate::symmetry::{Orientation, View};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs;
 
-pub struct JigsawSolution {
-    jigsaws: Vec<Jigsaw>,
-    frame: Vec<Vec<Option<Jigsaw>>>,
-}
+/// The shape of a sea monster, as it would appear in the image.
+const SEA_MONSTER: &str = "                  # \n#    ##    ##    ###\n #  #  #  #  #  #   ";
+
+pub struct JigsawSolution;
 
-/// Explain the rules of this day
+/// The satellite's camera sent an image, but it arrived as a bunch of
+/// square tiles which have been rotated and flipped at random. The
+/// edges of neighboring tiles always match, so put the image together.
 impl Solution for JigsawSolution {
-    type Data = Mutex<Self>;
+    type Data = Vec<Jigsaw>;
     type Output = u64;
 
     const MESSAGE_A: &'static str = "Product of corners";
-    const MESSAGE_B: &'static str = "Number of monsters";
+    const MESSAGE_B: &'static str = "Water roughness";
 
-    fn from_string(s: &str) -> Mutex<Self> {
-        Mutex::new(Self {
-            jigsaws: Self::map_clusters(s, |c| Jigsaw::parse(c).expect(c)),
-            frame: Vec::new(),
-        })
+    fn from_string(s: &str) -> Vec<Jigsaw> {
+        Self::map_clusters(s, |c| Jigsaw::parse(c).expect(c))
     }
 
-    /// Assemble the image. Multiple the IDs of the corners.
-    fn get_solution_a(data: &Mutex<Self>) -> Option<u64> {
-        let mut data = data.lock().unwrap();
-        let a = data.jigsaws.len();
-        // Assuming this image is relatively square.
-        let l = ((a as f32).sqrt() * 2.0 + 1.0) as usize;
-        let c = l / 2 + 1;
-        let mut frame = vec![vec![None; l]; l];
-        // Start in the center of the frame and work
-        // around it so we don't have to shift.
-        frame[c][c] = data.jigsaws.pop();
-        let mut n = a;
-
-        // The current line of thought is that, when we're searching in order,
-        // if we ever *don't* find a validate candidate to place, then we've
-        // probably hit the edge of the image.
-
-        // Look into creating a "frame iterator" with functionalities for
-        // skipping to the next row. Could be useful. Then, you would just
-        // need to determine direction using current / last coordinates.
-
-        for i in 0..a {
-            let n2 = data.jigsaws.len();
-            if n != n2 {
-                println!("num left: {}", n2);
-            }
-            n = n2;
-            if data.jigsaws.is_empty() {
-                println!("complete @ {}", i);
-                break;
-            }
-            for y in (0..(l - 1)).step_by(2) {
-                let mut last = (0, y);
-                // First check down.
-                if frame[last.1][last.0].is_some() && frame[y][0].is_none() {
-                    let r = frame[last.1][last.0].as_ref().unwrap();
-                    if let Some(idx) = r.find_down(&mut data.jigsaws) {
-                        frame[y][0] = Some(data.jigsaws.remove(idx));
-                    }
-                }
-                last = (0, y);
-                // And then right.
-                for x in 1..l {
-                    if frame[last.1][last.0].is_some() && frame[y][x].is_none() {
-                        let r = frame[last.1][last.0].as_ref().unwrap();
-                        if let Some(idx) = r.find_right(&mut data.jigsaws) {
-                            frame[y][x] = Some(data.jigsaws.remove(idx));
-                        } else {
-                            break;
-                        }
-                    }
-                    last = (x, y);
-                }
-                // And then down again.
-                let y = y + 1;
-                if frame[last.1][last.0].is_some() && frame[y][last.0].is_none() {
-                    let r = frame[last.1][last.0].as_ref().unwrap();
-                    if let Some(idx) = r.find_down(&mut data.jigsaws) {
-                        frame[y][0] = Some(data.jigsaws.remove(idx));
-                    }
-                }
-                last = (last.0, y);
-                // And then left.
-                for x in (1..last.0).rev() {
-                    if frame[last.1][last.0].is_some() && frame[y][x].is_none() {
-                        let r = frame[last.1][last.0].as_ref().unwrap();
-                        if let Some(idx) = r.find_left(&mut data.jigsaws) {
-                            frame[y][x] = Some(data.jigsaws.remove(idx));
-                        } else {
-                            break;
-                        }
-                    }
-                    last = (x, y);
-                }
-            }
+    /// Assemble the image. Multiply the IDs of the corners.
+    fn get_solution_a(data: &Vec<Jigsaw>) -> Option<u64> {
+        Some(Image::assemble(data)?.corner_product())
+    }
+
+    /// Find the sea monsters. Count the `#`s which aren't part of any.
+    fn get_solution_b(data: &Vec<Jigsaw>) -> Option<u64> {
+        let search = Image::assemble(data)?.search(&Pattern::default());
+        Some(search.roughness() as u64)
+    }
+}
+
+/// Searches the assembled puzzle input for the pattern in a file, or
+/// for sea monsters if there isn't one, and draws every match.
+pub fn search(pattern: Option<&str>) -> Result<String, String> {
+    let search = assemble()?.search(&load_pattern(pattern)?);
+    Ok(format!(
+        "{}{} matches after turning {:?}. Roughness: {}\n",
+        search,
+        search.matches.len(),
+        search.orientation,
+        search.roughness()
+    ))
+}
+
+/// Draws the assembled puzzle input with every jigsaw outlined, or with
+/// every match of a pattern highlighted for part b. The pattern is the
+/// sea monster unless a file is given.
+pub fn picture(part_b: bool, pattern: Option<&str>) -> Result<Picture, String> {
+    let image = assemble()?;
+    if part_b {
+        Ok(Picture::from(&image.search(&load_pattern(pattern)?)))
+    } else {
+        Ok(Picture::from(&image))
+    }
+}
+
+fn assemble() -> Result<Image, String> {
+    let data = JigsawSolution::from_file("jigsaw.txt");
+    Image::assemble(&data).ok_or_else(|| "The jigsaws don't fit together.".to_string())
+}
+
+fn load_pattern(path: Option<&str>) -> Result<Pattern, String> {
+    match path {
+        Some(path) => Pattern::from_file(path),
+        None => Ok(Pattern::default()),
+    }
+}
+
+/// A fully assembled set of jigsaws, each oriented such that all of
+/// its edges line up with its neighbors.
+#[derive(Debug, Clone)]
+pub struct Image {
+    frame: Grid<Jigsaw>,
+}
+
+impl Image {
+    /// Attempts to place every jigsaw into a square frame. Yields `None`
+    /// if the number of jigsaws is not square or no arrangement exists.
+    pub fn assemble(jigsaws: &[Jigsaw]) -> Option<Self> {
+        let size = (jigsaws.len() as f64).sqrt().round() as usize;
+        if size * size != jigsaws.len() || size == 0 {
+            return None;
         }
+        let mut assembler = Assembler::new(jigsaws, size);
+        if !assembler.place(0) {
+            return None;
+        }
+        let mut placed = assembler.placed.into_iter();
+        let rows = (0..size).map(|_| placed.by_ref().take(size).collect());
+        let frame = Grid::from_rows(rows.collect())?;
+        Some(Self { frame })
+    }
+
+    /// Multiplies the IDs of the jigsaws in each corner.
+    pub fn corner_product(&self) -> u64 {
+        let (w, h) = (self.frame.width() - 1, self.frame.height() - 1);
+        let corners = [(0, 0), (w, 0), (0, h), (w, h)];
+        corners.iter().map(|&p| self.frame[p].id).product()
+    }
+
+    /// Joins every jigsaw into a single grid of pixels. The borders of each
+    /// jigsaw are only there to help line them up and can be removed.
+    pub fn pixels(&self, borders: bool) -> Grid<bool> {
+        let trim = if borders { 0 } else { 1 };
+        let n = self.frame[(0, 0)].jig.width() - 2 * trim;
+        let (width, height) = (self.frame.width() * n, self.frame.height() * n);
+        Grid::from_fn(width, height, |x, y| {
+            self.frame[(x / n, y / n)].jig[(x % n + trim, y % n + trim)]
+        })
+    }
 
+    /// Searches every orientation of the image, without borders, for a pattern.
+    pub fn search(&self, pattern: &Pattern) -> Search {
+        Search::new(&self.pixels(false), pattern)
+    }
+}
 
-        // let mut pos = (c, c);
-        // let mut last = frame[c][c].as_ref().unwrap();
-        // for _ in 0..(data.jigsaws.len().pow(2)) {
-        //     let mut piece = data.jigsaws.remove(0);
-        //     let found = piece.for_each_translation(|p| {
-        //         if pos.0 + 1 < l && frame[pos.1][pos.0 + 1].is_none() {
-        //             if last.check_right(p) {
-        //                 println!("b {:?}", pos);
-        //                 pos.0 += 1;
-        //                 println!("a {:?}", pos);
-        //                 return true;
-        //             }
-        //         } else if pos.0 > 0 && frame[pos.1][pos.0 - 1].is_none() {
-        //             if last.check_left(p) {
-        //                 pos.0 -= 1;
-        //                 return true;
-        //             }
-        //         } else if pos.1 + 1 > l && frame[pos.1 + 1][pos.0].is_none() {
-        //             if last.check_up(p) {
-        //                 pos.1 += 1;
-        //                 return true;
-        //             }
-        //         } else if pos.1 > 0 && frame[pos.1 - 1][pos.0].is_none() {
-        //             if last.check_down(p) {
-        //                 pos.1 -= 1;
-        //                 return true;
-        //             }
-        //         }
-        //         false
-        //     });
-        //     if found {
-        //         frame[pos.1][pos.0] = Some(piece);
-        //         last = frame[pos.1][pos.0].as_ref().unwrap();
-        //     } else {
-        //         data.jigsaws.push(piece);
-        //     }
-        // }
-        display_frame(&frame);
-        None
-    }
-
-    /// Explain solution B
-    fn get_solution_b(_: &Mutex<Self>) -> Option<u64> {
-        None
+/// Draws the image with its borders, outlining each jigsaw.
+impl From<&Image> for Picture {
+    fn from(image: &Image) -> Self {
+        let size = image.frame[(0, 0)].jig.width();
+        Picture::new(&image.pixels(true)).with_tiles(size)
     }
 }
 
-fn display(jigsaw: &Jigsaw) {
-    for line in &jigsaw.jig {
-        print!("[ ");
-        for b in line {
-            if *b {
-                print!("# ")
-            } else {
-                print!(". ")
+/// Displays the image with its borders, leaving a gap between jigsaws.
+impl Display for Image {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for row in self.frame.rows() {
+            for y in 0..row[0].jig.height() {
+                let line: Vec<String> = row
+                    .iter()
+                    .map(|j| {
+                        j.jig
+                            .row(y)
+                            .iter()
+                            .map(|&b| if b { '#' } else { '.' })
+                            .collect()
+                    })
+                    .collect();
+                writeln!(f, "{}", line.join(" "))?;
             }
+            writeln!(f)?;
         }
-        println!("]")
+        Ok(())
     }
 }
 
-fn display_frame(frame: &Vec<Vec<Option<Jigsaw>>>) {
-    let c = frame.len() / 2 + 1;
-    let a = frame[c][c].as_ref().unwrap().jig.len();
-    let w = a * frame.len();
-    for x in 0..w {
-        print!("[ ");
-        for y in 0..w {
-            if let Some(jig) = &frame[x / a][y / a] {
-                if jig.jig[x % a][y % a] {
-                    print!("# ");
-                } else {
-                    print!("  ");
+/// A shape to search for inside of an image. Only the `#`s in a pattern
+/// need to match. Every other character can be anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// The position of every `#` as `(x, y)`.
+    cells: Vec<(usize, usize)>,
+    width: usize,
+    height: usize,
+}
+
+impl Pattern {
+    /// Yields `None` if there are no `#`s, since an empty pattern would
+    /// match everywhere.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut cells = Vec::new();
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if c == '#' {
+                    cells.push((x, y));
                 }
-            } else {
-                print!("  ");
             }
         }
-        println!("]")
+        let width = cells.iter().map(|&(x, _)| x + 1).max()?;
+        let height = cells.iter().map(|&(_, y)| y + 1).max()?;
+        Some(Self {
+            cells,
+            width,
+            height,
+        })
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct Jigsaw {
-    id: u64,
-    jig: Vec<Vec<bool>>,
+    /// Reads a pattern from a text file, e.g. to search for other shapes.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let s = fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+        Self::parse(&s).ok_or(format!("{} has no `#`s to search for.", path))
+    }
+
+    /// Finds the top-left corner of every match in an image as `(x, y)`.
+    fn find_all(&self, pixels: &View<bool>) -> Vec<(usize, usize)> {
+        let height = (pixels.height() + 1).saturating_sub(self.height);
+        let width = (pixels.width() + 1).saturating_sub(self.width);
+        let mut matches = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if self.cells.iter().all(|&(px, py)| pixels[(x + px, y + py)]) {
+                    matches.push((x, y));
+                }
+            }
+        }
+        matches
+    }
 }
 
-impl Jigsaw {
-    fn parse(s: &str) -> Option<Self> {
-        let mut lines = s.lines();
-        let title = &lines.next()?[5..9];
-        let id = title.parse().ok()?;
-        let jig = lines.map(|s| s.chars().map(is_pixel).collect()).collect();
-        Some(Self { id, jig })
+/// The sea monster.
+impl Default for Pattern {
+    fn default() -> Self {
+        Self::parse(SEA_MONSTER).unwrap()
     }
+}
 
-    // Check every possible translation until one returns true.
-    fn any_trans<F: FnMut(&Self) -> bool>(&mut self, mut f: F) -> bool {
-        for _ in 0..4 {
-            if f(self) {
-                return true;
+/// The result of searching for a pattern in the orientation of an image
+/// where it was found the most.
+#[derive(Debug, Clone)]
+pub struct Search {
+    /// How the image was rotated and flipped to find the matches.
+    pub orientation: Orientation,
+    /// The image, rotated and flipped in the same way as the matches.
+    pub pixels: Grid<bool>,
+    /// Every pixel which is covered by at least one match.
+    pub marked: Grid<bool>,
+    /// The top-left corner of each match as `(x, y)`.
+    pub matches: Vec<(usize, usize)>,
+}
+
+impl Search {
+    fn new(pixels: &Grid<bool>, pattern: &Pattern) -> Self {
+        let (orientation, matches) = Orientation::all()
+            .iter()
+            .map(|&o| (o, pattern.find_all(&o.view(pixels))))
+            .fold(
+                None,
+                |best: Option<(Orientation, Vec<_>)>, (o, m)| match best {
+                    Some(b) if b.1.len() >= m.len() => Some(b),
+                    _ => Some((o, m)),
+                },
+            )
+            .unwrap();
+        let pixels = orientation.apply(pixels);
+        let mut marked = Grid::new(pixels.width(), pixels.height(), false);
+        for &(x, y) in &matches {
+            for &(px, py) in &pattern.cells {
+                marked[(x + px, y + py)] = true;
             }
-            self.rotate();
         }
-        self.flip_x();
-        if f(self) {
-            return true;
+        Self {
+            orientation,
+            pixels,
+            marked,
+            matches,
         }
-        self.flip_x();
-        self.flip_y();
-        if f(self) {
-            return true;
-        }
-        self.flip_y();
-        false
     }
 
-    fn rotate(&mut self) {
-        let n = self.jig.len();
-        for x in 0..(n / 2) {
-            for y in x..(n - x - 1) {
-                let old = self.jig[x][y];
-                self.jig[x][y] = self.jig[y][n - 1 - x];
-                self.jig[y][n - 1 - x] = self.jig[n - 1 - x][n - 1 - y];
-                self.jig[n - 1 - x][n - 1 - y] = self.jig[n - 1 - y][x];
-                self.jig[n - 1 - y][x] = old;
-            }
-        }
+    /// The number of `#`s which are not part of any match.
+    pub fn roughness(&self) -> usize {
+        let pixels = self.pixels.iter();
+        let marked = self.marked.iter();
+        pixels.zip(marked).filter(|&(&p, &m)| p && !m).count()
     }
+}
 
-    fn flip_x(&mut self) {
-        let n = self.jig.len();
-        for x in 0..(n / 2) {
-            for y in 0..n {
-                let old = self.jig[x][y];
-                self.jig[x][y] = self.jig[n - 1 - x][y];
-                self.jig[n - 1 - x][y] = old;
-            }
-        }
+/// Draws the searched image with every match highlighted.
+impl From<&Search> for Picture {
+    fn from(search: &Search) -> Self {
+        let marked = search.marked.positions().filter(|&(_, &m)| m);
+        let marked = marked.map(|(p, _)| p);
+        Picture::new(&search.pixels).with_highlights(marked)
     }
+}
 
-    fn flip_y(&mut self) {
-        let n = self.jig.len();
-        for y in 0..(n / 2) {
-            for x in 0..n {
-                let old = self.jig[x][y];
-                self.jig[x][y] = self.jig[x][n - 1 - y];
-                self.jig[x][n - 1 - y] = old;
-            }
+/// Displays the image with every match drawn as `O`s.
+impl Display for Search {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (pixels, marked) in self.pixels.rows().zip(self.marked.rows()) {
+            let line: String = pixels
+                .iter()
+                .zip(marked)
+                .map(|(&p, &m)| match (p, m) {
+                    (_, true) => 'O',
+                    (true, false) => '#',
+                    (false, false) => '.',
+                })
+                .collect();
+            writeln!(f, "{}", line)?;
         }
+        Ok(())
     }
+}
+
+/// Places jigsaws into a frame one row at a time, backtracking whenever
+/// a jigsaw can't be placed.
+struct Assembler<'a> {
+    jigsaws: &'a [Jigsaw],
+    /// Every jigsaw with a given edge, in any orientation.
+    index: HashMap<u32, Vec<usize>>,
+    size: usize,
+    used: Vec<bool>,
+    /// Every jigsaw placed so far, in order from left to right, top to bottom.
+    placed: Vec<Jigsaw>,
+}
 
-    fn find_down(&self, from: &mut Vec<Self>) -> Option<usize> {
-        for (i, jig) in from.iter_mut().enumerate() {
-            if jig.any_trans(|f| self.check_down(f)) {
-                return Some(i);
+impl<'a> Assembler<'a> {
+    fn new(jigsaws: &'a [Jigsaw], size: usize) -> Self {
+        let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (i, jig) in jigsaws.iter().enumerate() {
+            for sig in &jig.signatures() {
+                // A jigsaw can have the same edge more than once.
+                let jigsaws = index.entry(*sig).or_default();
+                if jigsaws.last() != Some(&i) {
+                    jigsaws.push(i);
+                }
             }
         }
-        None
+        Self {
+            jigsaws,
+            index,
+            size,
+            used: vec![false; jigsaws.len()],
+            placed: Vec::new(),
+        }
     }
 
-    fn find_left(&self, from: &mut Vec<Self>) -> Option<usize> {
-        for (i, jig) in from.iter_mut().enumerate() {
-            if jig.any_trans(|f| self.check_left(f)) {
-                return Some(i);
+    /// Places a jigsaw at `pos` and every position after it.
+    fn place(&mut self, pos: usize) -> bool {
+        if pos == self.size * self.size {
+            return true;
+        }
+        let (y, x) = (pos / self.size, pos % self.size);
+        let candidates = if x > 0 {
+            self.matching(self.placed[pos - 1].right())
+        } else if y > 0 {
+            self.matching(self.placed[pos - self.size].bottom())
+        } else {
+            self.corners()
+        };
+        for i in candidates {
+            if self.used[i] {
+                continue;
+            }
+            for jig in self.jigsaws[i].orientations() {
+                if !self.fits(&jig, x, y) {
+                    continue;
+                }
+                self.used[i] = true;
+                self.placed.push(jig);
+                if self.place(pos + 1) {
+                    return true;
+                }
+                self.placed.pop();
+                self.used[i] = false;
             }
         }
-        None
+        false
     }
 
-    fn find_right(&self, from: &mut Vec<Self>) -> Option<usize> {
-        for (i, jig) in from.iter_mut().enumerate() {
-            if jig.any_trans(|f| self.check_right(f)) {
-                return Some(i);
-            }
+    /// Determines whether a jigsaw lines up with the jigsaws to the left and
+    /// above it. Edges along the outside of the frame must not match anything.
+    fn fits(&self, jig: &Jigsaw, x: usize, y: usize) -> bool {
+        let pos = y * self.size + x;
+        let left = if x > 0 {
+            jig.left() == self.placed[pos - 1].right()
+        } else {
+            self.is_outside(jig.left())
+        };
+        let top = if y > 0 {
+            jig.top() == self.placed[pos - self.size].bottom()
+        } else {
+            self.is_outside(jig.top())
+        };
+        left && top
+    }
+
+    fn matching(&self, edge: u32) -> Vec<usize> {
+        let sig = canonical(edge, self.jigsaws[0].jig.width());
+        self.index.get(&sig).cloned().unwrap_or_default()
+    }
+
+    fn is_outside(&self, edge: u32) -> bool {
+        self.matching(edge).len() == 1
+    }
+
+    /// Every jigsaw with at least two edges that don't match any others.
+    fn corners(&self) -> Vec<usize> {
+        (0..self.jigsaws.len())
+            .filter(|&i| {
+                let sigs = self.jigsaws[i].signatures();
+                sigs.iter().filter(|sig| self.index[sig].len() == 1).count() >= 2
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Jigsaw {
+    id: u64,
+    jig: Grid<bool>,
+}
+
+impl Jigsaw {
+    /// Yields `None` unless the jigsaw is square and each edge fits in a
+    /// signature.
+    fn parse(s: &str) -> Option<Self> {
+        let mut lines = s.lines();
+        let title = lines.next()?.trim();
+        let id = title
+            .strip_prefix("Tile ")?
+            .strip_suffix(':')?
+            .parse()
+            .ok()?;
+        let jig = lines.map(|s| s.chars().map(is_pixel).collect()).collect();
+        let jig = Grid::from_rows(jig)?;
+        let size = jig.width();
+        if size != jig.height() || size == 0 || size > MAX_EDGE {
+            return None;
         }
-        None
+        Some(Self { id, jig })
+    }
+
+    /// Generates all eight rotations and reflections of this jigsaw.
+    fn orientations(&self) -> Vec<Self> {
+        let id = self.id;
+        let all = Orientation::all();
+        all.iter()
+            .map(|o| Self {
+                id,
+                jig: o.apply(&self.jig),
+            })
+            .collect()
     }
 
-    fn check_left(&self, other: &Self) -> bool {
-        return self.zip(other)
-            .all(|(s_vec, o_vec)| s_vec[0] == o_vec[o_vec.len() - 1]);
+    /// The signatures of all four edges, which are the same in any orientation.
+    fn signatures(&self) -> [u32; 4] {
+        let n = self.jig.width();
+        [self.top(), self.bottom(), self.left(), self.right()].map(|e| canonical(e, n))
     }
 
-    fn check_right(&self, other: &Self) -> bool {
-        return self.zip(other)
-            .all(|(s_vec, o_vec)| s_vec[s_vec.len() - 1] == o_vec[0]);
+    /// The top edge as bits, read from left to right.
+    fn top(&self) -> u32 {
+        to_bits(self.jig.row(0).iter().copied())
     }
 
-    fn check_up(&self, other: &Self) -> bool {
-        self.jig[0] == other.jig[other.jig.len() - 1]
+    /// The bottom edge as bits, read from left to right.
+    fn bottom(&self) -> u32 {
+        to_bits(self.jig.row(self.jig.height() - 1).iter().copied())
     }
 
-    fn check_down(&self, other: &Self) -> bool {
-        self.jig[self.jig.len() - 1] == other.jig[0]
+    /// The left edge as bits, read from top to bottom.
+    fn left(&self) -> u32 {
+        to_bits(self.jig.column(0).copied())
     }
 
-    fn zip<'a>(&'a self, other: &'a Self) -> Zip<Iter<'a, Vec<bool>>, Iter<'a, Vec<bool>>> {
-        self.jig.iter().zip(other.jig.iter())
+    /// The right edge as bits, read from top to bottom.
+    fn right(&self) -> u32 {
+        to_bits(self.jig.column(self.jig.width() - 1).copied())
     }
 }
 
+/// The longest edge which fits in a signature.
+const MAX_EDGE: usize = 32;
+
+fn to_bits<I: Iterator<Item = bool>>(edge: I) -> u32 {
+    edge.fold(0, |bits, b| bits << 1 | b as u32)
+}
+
+/// Edges are the same whether they are read forward or backward. `len`
+/// must be between 1 and `MAX_EDGE`.
+fn canonical(edge: u32, len: usize) -> u32 {
+    let reversed = edge.reverse_bits() >> (32 - len);
+    edge.min(reversed)
+}
+
 fn is_pixel(c: char) -> bool {
     match c {
         '.' => false,
@@ -314,10 +484,215 @@ fn is_pixel(c: char) -> bool {
 
 #[test]
 fn test_solution_a() {
-    // assert_eq!(None, JigsawSolution::get_solution_a(&""))
+    let (jigsaws, corners) = get_example(3, 10);
+    assert_eq!(JigsawSolution::get_solution_a(&jigsaws).unwrap(), corners);
+    let (jigsaws, corners) = get_example(12, 16);
+    assert_eq!(JigsawSolution::get_solution_a(&jigsaws).unwrap(), corners);
+    assert!(JigsawSolution::get_solution_a(&jigsaws[1..].to_vec()).is_none());
+}
+
+#[test]
+fn test_parse() {
+    let tile = |width: usize, height: usize| {
+        let row = format!("\n{}", "#".repeat(width));
+        format!("Tile 7:{}", row.repeat(height))
+    };
+    let jigsaw = Jigsaw::parse(&tile(32, 32)).unwrap();
+    assert_eq!(jigsaw.signatures()[0], u32::MAX);
+    assert!(Jigsaw::parse(&tile(33, 33)).is_none());
+    assert!(Jigsaw::parse(&tile(3, 4)).is_none());
+    assert!(Jigsaw::parse(&tile(0, 0)).is_none());
+    // Every edge is the same, but it still only belongs to one jigsaw,
+    // which is in all four corners.
+    let image = Image::assemble(&[jigsaw]).unwrap();
+    assert_eq!(image.corner_product(), 7 * 7 * 7 * 7);
+}
+
+#[test]
+fn test_worked_example() {
+    let example = "Tile 2311:
+..##.#..#.
+##..#.....
+#...##..#.
+####.#...#
+##.##.###.
+##...#.###
+.#.#.#..##
+..#....#..
+###...#.#.
+..###..###
+
+Tile 1951:
+#.##...##.
+#.####...#
+.....#..##
+#...######
+.##.#....#
+.###.#####
+###.##.##.
+.###....#.
+..#.#..#.#
+#...##.#..
+
+Tile 1171:
+####...##.
+#..##.#..#
+##.#..#.#.
+.###.####.
+..###.####
+.##....##.
+.#...####.
+#.##.####.
+####..#...
+.....##...
+
+Tile 1427:
+###.##.#..
+.#..#.##..
+.#.##.#..#
+#.#.#.##.#
+....#...##
+...##..##.
+...#.#####
+.#.####.#.
+..#..###.#
+..##.#..#.
+
+Tile 1489:
+##.#.#....
+..##...#..
+.##..##...
+..#...#...
+#####...#.
+#..#.#.#.#
+...#.#.#..
+##.#...##.
+..##.##.##
+###.##.#..
+
+Tile 2473:
+#....####.
+#..#.##...
+#.##..#...
+######.#.#
+.#...#.#.#
+.#########
+.###.#..#.
+########.#
+##...##.#.
+..###.#.#.
+
+Tile 2971:
+..#.#....#
+#...###...
+#.#.###...
+##.##..#..
+.#####..##
+.#..####.#
+#..#.#..#.
+..####.###
+..#.#.###.
+...#.#.#.#
+
+Tile 2729:
+...#.#.#.#
+####.#....
+..#.#.....
+....#..#.#
+.##..##.#.
+.#.####...
+####.#.#..
+##.####...
+##..#.##..
+#.##...##.
+
+Tile 3079:
+#.#.#####.
+.#..######
+..#.......
+######....
+####.#..#.
+.#...#.##.
+#.#####.##
+..#.###...
+..#.......
+..#.###...";
+    let data = JigsawSolution::from_string(example);
+    assert_eq!(JigsawSolution::get_solution_a(&data), Some(20899048083289));
+    assert_eq!(JigsawSolution::get_solution_b(&data), Some(273));
 }
 
 #[test]
 fn test_solution_b() {
-    // assert_eq!(None, JigsawSolution::get_solution_b(&""))
+    // Hide two monsters in a rotated image and make sure they are found.
+    let mut pixels = Grid::new(24, 24, false);
+    let monster = Pattern::default();
+    for &(x, y) in &[(1, 2), (3, 10)] {
+        for &(px, py) in &monster.cells {
+            pixels[(x + px, y + py)] = true;
+        }
+    }
+    pixels[(20, 20)] = true;
+    let pixels = Orientation::new(1, true).apply(&pixels);
+
+    let search = Search::new(&pixels, &monster);
+    assert_eq!(search.orientation, Orientation::new(1, true).inverse());
+    assert_eq!(search.matches, vec![(1, 2), (3, 10)]);
+    assert_eq!(search.roughness(), 1);
+    let line = search.to_string().lines().nth(2).unwrap().to_string();
+    assert_eq!(line, format!("{}O{}", ".".repeat(19), ".".repeat(4)));
+
+    // Any other shape can be searched for, e.g. a diagonal line.
+    let diagonal = Pattern::parse("#..\n.#.\n..#\n").unwrap();
+    assert_eq!(Some(diagonal.clone()), Pattern::parse("#\n #\n  #"));
+    let mut pixels = Grid::new(5, 4, false);
+    for i in 0..4 {
+        pixels[(i + 1, i)] = true;
+    }
+    let search = Search::new(&pixels, &diagonal);
+    assert_eq!(search.matches, vec![(1, 0), (2, 1)]);
+    assert_eq!(search.roughness(), 0);
+    assert_eq!(search.to_string(), ".O...\n..O..\n...O.\n....O\n");
+    assert!(Pattern::parse("...\n. .\n").is_none());
+    assert!(load_pattern(Some("missing_pattern.txt")).is_err());
+}
+
+/// Cuts a random image into `size * size` jigsaws which share their
+/// edges, then shuffles and reorients them. Also yields the product
+/// of the IDs in each corner.
+#[cfg(test)]
+fn get_example(size: usize, len: usize) -> (Vec<Jigsaw>, u64) {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(20);
+    let width = size * (len - 1) + 1;
+    let image = Grid::from_fn(width, width, |_, _| rng.gen());
+    let mut jigsaws = Vec::new();
+    let mut corners = 1;
+    for y in 0..size {
+        for x in 0..size {
+            let (top, left) = (y * (len - 1), x * (len - 1));
+            let jig = Grid::from_fn(len, len, |x, y| image[(left + x, top + y)]);
+            let id = 1000 + (y * size + x) as u64;
+            if (y == 0 || y == size - 1) && (x == 0 || x == size - 1) {
+                corners *= id;
+            }
+            let orientations = Jigsaw { id, jig }.orientations();
+            jigsaws.push(orientations.choose(&mut rng).unwrap().clone());
+        }
+    }
+    jigsaws.shuffle(&mut rng);
+    (jigsaws, corners)
+}
+
+#[test]
+fn test_export() {
+    let (jigsaws, _) = get_example(2, 10);
+    let image = Image::assemble(&jigsaws).unwrap();
+    let pbm = Picture::from(&image).to_pbm();
+    assert!(pbm.starts_with("P1\n20 20\n"));
+    let svg = Picture::from(&image.search(&Pattern::default())).to_svg();
+    assert!(svg.starts_with("<svg"));
 }
@@ -4,14 +4,35 @@ use crate::solutions::masks::Override::*;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs;
 
 lazy_static! {
     /// The pattern used for parsing mask assignments.
-    static ref MASK_PATTERN: Regex = Regex::new(r"mask = ([01X]{36})").unwrap();
+    static ref MASK_PATTERN: Regex = Regex::new(r"mask = ([01X]+)").unwrap();
     /// The pattern used for parsing memory assignments.
     static ref MEM_PATTERN: Regex = Regex::new(r"mem\[(\d*)\] = (\d*)").unwrap();
 }
 
+/// The number of bits in each word, unless told otherwise.
+pub const WIDTH: usize = 36;
+
+/// The largest supported number of bits in each word.
+pub const MAX_WIDTH: usize = 64;
+
+/// The program from the puzzle's first example.
+const EXAMPLE_A: &str = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+mem[8] = 11
+mem[7] = 101
+mem[8] = 0";
+
+/// The program from the puzzle's second example.
+const EXAMPLE_B: &str = "mask = 000000000000000000000000000000X1001X
+mem[42] = 100
+mask = 00000000000000000000000000000000X0XX
+mem[26] = 1";
+
 pub struct MaskSolution;
 
 /// The computer system where the docker is landing isn't compatible with
@@ -34,52 +55,148 @@ impl Solution for MaskSolution {
 
     /// Apply the current mask to each memory assignment. Get the sum.
     fn get_solution_a(data: &Vec<Assignment>) -> Option<usize> {
-        Some(MemDecoder::new(data).run().mem_sum())
+        MemDecoder::new(data, WIDTH).run().mem_sum()
     }
 
     /// Expand each X in the every mask. Apply it to the memory addresses.
+    /// The addresses are never listed out. Each write covers a pattern of
+    /// addresses instead, which later writes cut pieces out of.
     fn get_solution_b(data: &Vec<Assignment>) -> Option<usize> {
-        Some(MemDecoder::new(data).run_v2().mem_sum())
+        FloatingDecoder::run(data, WIDTH).mem_sum()
     }
 }
 
+/// Runs a program with either decoder, optionally tracing every write,
+/// then dumps the memory. The v2 decoder never lists addresses out, so it
+/// dumps patterns of addresses instead, and can look up `address`. Runs
+/// the example for the part unless given a `path` or told to use the
+/// input.
+pub fn explain(
+    input: bool,
+    path: Option<&str>,
+    part_b: bool,
+    width: usize,
+    radix: Option<&str>,
+    address: Option<usize>,
+    trace: bool,
+) -> Result<String, String> {
+    let program = match path {
+        Some(path) => fs::read_to_string(path).map_err(|e| e.to_string())?,
+        None if input => fs::read_to_string("input/masks.txt").map_err(|e| e.to_string())?,
+        None if part_b => EXAMPLE_B.to_string(),
+        None => EXAMPLE_A.to_string(),
+    };
+    if width > MAX_WIDTH {
+        return Err(format!("Words can't be over {} bits.", MAX_WIDTH));
+    }
+    let data = program
+        .lines()
+        .map(|l| Assignment::parse_width(l, width).ok_or(format!("Invalid line: {}", l)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let radix = match radix {
+        Some(r) => Radix::parse(r).ok_or(format!("Unknown radix: {}", r))?,
+        None => Radix::Decimal,
+    };
+
+    let mut out = String::new();
+    let (writes, sum) = if part_b {
+        let decoder = FloatingDecoder::run_traced(&data, width);
+        out += &decoder.dump(radix);
+        out += &format!("Wrote to {} addresses\n", decoder.len());
+        if let Some(address) = address {
+            let address = truncate(address, width);
+            match decoder.get(address) {
+                Some(value) => out += &format!("mem[{}] = {}\n", address, value),
+                None => out += &format!("mem[{}] was never written\n", address),
+            }
+        }
+        (
+            decoder.trace().unwrap_or_default().to_vec(),
+            decoder.mem_sum(),
+        )
+    } else {
+        let decoder = MemDecoder::new(&data, width).traced().run();
+        out += &decoder.dump(radix);
+        (
+            decoder.trace().unwrap_or_default().to_vec(),
+            decoder.mem_sum(),
+        )
+    };
+    if trace {
+        let writes: Vec<String> = writes.iter().map(Write::to_string).collect();
+        out = writes.concat() + &out;
+    }
+    match sum {
+        Some(sum) => out += &format!("Sum: {}\n", sum),
+        None => out += "The sum doesn't fit in a usize\n",
+    }
+    Ok(out)
+}
+
 pub struct MemDecoder<'a> {
     assignments: &'a Vec<Assignment>,
     mem: HashMap<usize, usize>,
+    /// The number of bits in each word.
+    width: usize,
+    /// Every write so far, if tracing.
+    trace: Option<Vec<Write>>,
 }
 
 impl<'a> MemDecoder<'a> {
-    fn new(assignments: &'a Vec<Assignment>) -> Self {
+    /// Constructs a new decoder for words of `width` bits. Panics if the
+    /// width is over 64.
+    fn new(assignments: &'a Vec<Assignment>, width: usize) -> Self {
+        assert!(width <= MAX_WIDTH, "Words can't be over 64 bits.");
         Self {
             mem: HashMap::new(),
             assignments,
+            width,
+            trace: None,
         }
     }
 
+    /// Records every write, along with how the mask changed it.
+    fn traced(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
     fn run(mut self) -> Self {
-        let reference = [Unset; 36];
+        let reference = vec![Unset; self.width];
         let mut mask = &reference;
         for a in self.assignments {
             match a {
                 Mask(m) => mask = m,
                 Mem(i, v) => {
-                    let decoded = Override::decode_all(mask, *v);
-                    self.mem.insert(*i, decoded);
+                    let (address, value) = (truncate(*i, self.width), truncate(*v, self.width));
+                    let decoded = Override::decode_all(mask, value);
+                    self.mem.insert(address, decoded);
+                    if let Some(trace) = &mut self.trace {
+                        trace.push(Write::Value {
+                            mask: Override::format(mask),
+                            address,
+                            before: value,
+                            after: decoded,
+                        });
+                    }
                 }
             }
         }
         self
     }
 
+    /// Writes to every address a v2 mask expands into. Only used to check
+    /// the `FloatingDecoder`, since it blows up with many floating bits.
+    #[cfg(test)]
     fn run_v2(mut self) -> Self {
         let mut masks = Vec::new();
         for a in self.assignments {
             match a {
                 Mask(m) => masks = Override::expand(m),
                 Mem(i, v) => {
+                    let (address, value) = (truncate(*i, self.width), truncate(*v, self.width));
                     for m in &masks {
-                        let decoded = Override::decode_all(m, *i);
-                        self.mem.insert(decoded, *v);
+                        self.mem.insert(Override::decode_all(m, address), value);
                     }
                 }
             }
@@ -87,23 +204,331 @@ impl<'a> MemDecoder<'a> {
         self
     }
 
-    fn mem_sum(&self) -> usize {
-        self.mem.values().sum()
+    /// The sum of every value in memory, if it fits in a `usize`.
+    fn mem_sum(&self) -> Option<usize> {
+        let mut values = self.mem.values();
+        values.try_fold(0usize, |sum, &v| sum.checked_add(v))
+    }
+
+    /// Every address written to and its value, sorted by address.
+    fn entries(&self) -> Vec<(usize, usize)> {
+        let mut entries: Vec<(usize, usize)> = self.mem.iter().map(|(&a, &v)| (a, v)).collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// Lists the memory one address per line, sorted by address. Hex and
+    /// binary numbers are padded to the width of a word.
+    fn dump(&self, radix: Radix) -> String {
+        let mut dump = String::new();
+        for (address, value) in self.entries() {
+            dump += &format!(
+                "mem[{}] = {}\n",
+                radix.format(address, self.width),
+                radix.format(value, self.width)
+            );
+        }
+        dump
+    }
+
+    /// Every write so far, if tracing.
+    fn trace(&self) -> Option<&[Write]> {
+        self.trace.as_deref()
+    }
+}
+
+/// How to write out numbers when dumping memory.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Radix {
+    Decimal,
+    Hex,
+    Binary,
+}
+
+impl Radix {
+    /// Reads `dec`, `hex` or `bin`.
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "dec" => Some(Radix::Decimal),
+            "hex" => Some(Radix::Hex),
+            "bin" => Some(Radix::Binary),
+            _ => None,
+        }
+    }
+
+    fn format(self, num: usize, width: usize) -> String {
+        match self {
+            Radix::Decimal => num.to_string(),
+            Radix::Hex => format!("{:#0w$x}", num, w = width.div_ceil(4) + 2),
+            Radix::Binary => format!("{:#0w$b}", num, w = width + 2),
+        }
+    }
+}
+
+/// A single memory assignment, along with what the mask did to it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Write {
+    /// The mask changed the value written (v1).
+    Value {
+        mask: String,
+        address: usize,
+        before: usize,
+        after: usize,
+    },
+    /// The mask changed the address written to, possibly into many (v2).
+    Address {
+        mask: String,
+        before: usize,
+        after: Floating,
+        value: usize,
+    },
+}
+
+/// Lines the numbers up in binary under the mask.
+impl Display for Write {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Write::Value {
+                mask,
+                address,
+                before,
+                after,
+            } => {
+                writeln!(f, "mem[{}] = {}", address, before)?;
+                writeln!(f, "  value:  {:0w$b}", before, w = mask.len())?;
+                writeln!(f, "  mask:   {}", mask)?;
+                writeln!(f, "  result: {:0w$b} ({})", after, after, w = mask.len())
+            }
+            Write::Address {
+                mask,
+                before,
+                after,
+                value,
+            } => {
+                writeln!(f, "mem[{}] = {}", before, value)?;
+                writeln!(f, "  address: {:0w$b}", before, w = mask.len())?;
+                writeln!(f, "  mask:    {}", mask)?;
+                let result = after.format(mask.len());
+                writeln!(f, "  result:  {} ({} addresses)", result, after.len())
+            }
+        }
+    }
+}
+
+/// Drops any bits which don't fit in a word of `width` bits.
+fn truncate(num: usize, width: usize) -> usize {
+    match width {
+        MAX_WIDTH => num,
+        width => num & ((1 << width) - 1),
+    }
+}
+
+/// A v2 decoder which stores every write as a pattern of addresses, so
+/// floating bits never have to be expanded.
+pub struct FloatingDecoder {
+    /// Patterns which never overlap, along with the value at every address
+    /// they contain.
+    writes: Vec<(Floating, usize)>,
+    /// The number of bits in each word.
+    width: usize,
+    /// Every write, if tracing.
+    trace: Option<Vec<Write>>,
+}
+
+impl FloatingDecoder {
+    /// Decodes every assignment for words of `width` bits. Panics if the
+    /// width is over 64.
+    fn run(assignments: &[Assignment], width: usize) -> Self {
+        Self::run_with(assignments, width, None)
+    }
+
+    /// Like `run`, but records every write along with how the mask changed
+    /// its address.
+    fn run_traced(assignments: &[Assignment], width: usize) -> Self {
+        Self::run_with(assignments, width, Some(Vec::new()))
+    }
+
+    fn run_with(assignments: &[Assignment], width: usize, mut trace: Option<Vec<Write>>) -> Self {
+        assert!(width <= MAX_WIDTH, "Words can't be over 64 bits.");
+        let mut writes: Vec<(Floating, usize)> = Vec::new();
+        let mut mask = None;
+        for a in assignments {
+            match a {
+                Mask(m) => mask = Some(m),
+                Mem(i, v) => {
+                    let (address, value) = (truncate(*i, width), truncate(*v, width));
+                    let pattern = match mask {
+                        Some(m) => Floating::new(m, address),
+                        None => continue,
+                    };
+                    // Anything this write covers is overwritten.
+                    writes = writes
+                        .into_iter()
+                        .flat_map(|(p, old)| {
+                            p.subtract(&pattern).into_iter().map(move |p| (p, old))
+                        })
+                        .collect();
+                    writes.push((pattern, value));
+                    if let (Some(trace), Some(m)) = (&mut trace, mask) {
+                        trace.push(Write::Address {
+                            mask: Override::format(m),
+                            before: address,
+                            after: pattern,
+                            value,
+                        });
+                    }
+                }
+            }
+        }
+        Self {
+            writes,
+            width,
+            trace,
+        }
+    }
+
+    /// The value at a single address, if anything was written there.
+    fn get(&self, address: usize) -> Option<usize> {
+        let mut writes = self.writes.iter();
+        writes.find(|(p, _)| p.contains(address)).map(|&(_, v)| v)
+    }
+
+    /// The number of addresses written to. The patterns never overlap, so
+    /// this is at most 2^64.
+    fn len(&self) -> u128 {
+        self.writes.iter().map(|(p, _)| p.len()).sum()
+    }
+
+    /// The sum of every value in memory, if it fits in a `usize`.
+    fn mem_sum(&self) -> Option<usize> {
+        let mut writes = self.writes.iter();
+        let sum = writes.try_fold(0u128, |sum, (p, v)| {
+            sum.checked_add(p.len().checked_mul(*v as u128)?)
+        })?;
+        usize::try_from(sum).ok()
+    }
+
+    /// Lists the memory one pattern of addresses per line, sorted by their
+    /// fixed bits. Patterns are written like masks, while values are
+    /// written like `MemDecoder::dump`.
+    fn dump(&self, radix: Radix) -> String {
+        let mut writes = self.writes.clone();
+        writes.sort_unstable_by_key(|&(p, _)| (p.fixed, p.floating));
+        let mut dump = String::new();
+        for (pattern, value) in writes {
+            dump += &format!(
+                "mem[{}] = {}\n",
+                pattern.format(self.width),
+                radix.format(value, self.width)
+            );
+        }
+        dump
+    }
+
+    /// Every write, if tracing.
+    fn trace(&self) -> Option<&[Write]> {
+        self.trace.as_deref()
+    }
+}
+
+/// Every address matching some fixed bits, where the rest can be anything.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Floating {
+    /// The value of every fixed bit. Floating bits are always 0.
+    fixed: usize,
+    floating: usize,
+}
+
+impl Floating {
+    /// The addresses written to by a v2 mask.
+    fn new(mask: &[Override], address: usize) -> Self {
+        let mut fixed = address;
+        let mut floating = 0;
+        for (idx, o) in mask.iter().enumerate() {
+            let bit = Override::bit(mask, idx);
+            match o {
+                Set => fixed |= bit,
+                X => floating |= bit,
+                Unset => (),
+            }
+        }
+        Self {
+            fixed: fixed & !floating,
+            floating,
+        }
+    }
+
+    /// The number of addresses in the pattern, up to 2^64.
+    fn len(&self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    /// Writes the pattern like a mask of `width` bits, with an `X` for
+    /// every floating bit.
+    fn format(&self, width: usize) -> String {
+        let symbol = |i| match 1usize << i {
+            bit if self.floating & bit != 0 => 'X',
+            bit if self.fixed & bit != 0 => '1',
+            _ => '0',
+        };
+        (0..width).rev().map(symbol).collect()
+    }
+
+    fn contains(&self, address: usize) -> bool {
+        address & !self.floating == self.fixed
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        let shared = !(self.floating | other.floating);
+        (self.fixed ^ other.fixed) & shared == 0
+    }
+
+    /// Splits off every address not in `other`. The pieces never overlap.
+    fn subtract(&self, other: &Self) -> Vec<Self> {
+        if !self.intersects(other) {
+            return vec![*self];
+        }
+        let mut pieces = Vec::new();
+        let mut rest = *self;
+        let mut splits = self.floating & !other.floating;
+        // Fix each of these bits in turn. Whatever disagrees with `other`
+        // is kept and whatever agrees is split again.
+        while splits != 0 {
+            let bit = splits & splits.wrapping_neg();
+            splits &= !bit;
+            rest.floating &= !bit;
+            pieces.push(Self {
+                fixed: rest.fixed | (!other.fixed & bit),
+                floating: rest.floating,
+            });
+            rest.fixed |= other.fixed & bit;
+        }
+        pieces
     }
 }
 
 #[derive(Debug)]
 pub enum Assignment {
-    Mask([Override; 36]),
+    /// One override per bit, starting from the highest.
+    Mask(Vec<Override>),
     Mem(usize, usize),
 }
 
 impl Assignment {
     fn parse(s: &str) -> Option<Assignment> {
+        Self::parse_width(s, WIDTH)
+    }
+
+    /// Parses an assignment for words of `width` bits. Masks must have
+    /// exactly that many bits.
+    fn parse_width(s: &str, width: usize) -> Option<Assignment> {
         if MASK_PATTERN.is_match(s) {
             let captures = MASK_PATTERN.captures(s)?;
             let raw_mask = captures.get(1)?.as_str();
-            let mut mask = [Unset; 36];
+            if raw_mask.len() != width || width > MAX_WIDTH {
+                return None;
+            }
+            let mut mask = vec![Unset; width];
             for (i, ch) in raw_mask.chars().enumerate() {
                 match ch {
                     '1' => mask[i] = Set,
@@ -115,8 +540,8 @@ impl Assignment {
             return Some(Mask(mask));
         } else if MEM_PATTERN.is_match(s) {
             let captures = MEM_PATTERN.captures(s)?;
-            let idx: usize = captures.get(1)?.as_str().parse().unwrap();
-            let val: usize = captures.get(2)?.as_str().parse().unwrap();
+            let idx: usize = captures.get(1)?.as_str().parse().ok()?;
+            let val: usize = captures.get(2)?.as_str().parse().ok()?;
             return Some(Mem(idx, val));
         }
         None
@@ -131,10 +556,15 @@ pub enum Override {
 }
 
 impl Override {
-    fn decode(&self, idx: usize, mut num: usize) -> usize {
+    /// The bit at `idx` in a mask, where the first is the highest.
+    fn bit(mask: &[Override], idx: usize) -> usize {
+        1 << (mask.len() - 1 - idx)
+    }
+
+    fn decode(&self, bit: usize, mut num: usize) -> usize {
         match self {
-            Set => num |= 0x800000000 >> idx,
-            Unset => num &= !(0x800000000 >> idx),
+            Set => num |= bit,
+            Unset => num &= !bit,
             X => (),
         }
         num
@@ -142,12 +572,23 @@ impl Override {
 
     fn decode_all(mask: &[Override], mut num: usize) -> usize {
         for (idx, v) in mask.iter().enumerate() {
-            num = v.decode(idx, num);
+            num = v.decode(Self::bit(mask, idx), num);
         }
         num
     }
 
-    fn expand(mask: &[Override; 36]) -> Vec<[Self; 36]> {
+    /// Writes the mask the way it appears in the input.
+    fn format(mask: &[Override]) -> String {
+        let symbol = |o: &Override| match o {
+            Set => '1',
+            Unset => '0',
+            X => 'X',
+        };
+        mask.iter().map(symbol).collect()
+    }
+
+    #[cfg(test)]
+    fn expand(mask: &[Override]) -> Vec<Vec<Self>> {
         let mut masks = Vec::new();
         let wildcards: Vec<usize> = mask
             .iter()
@@ -170,8 +611,9 @@ impl Override {
         masks
     }
 
-    fn fill_xs(mask: &[Self; 36]) -> [Self; 36] {
-        let mut m = [Set; 36];
+    #[cfg(test)]
+    fn fill_xs(mask: &[Self]) -> Vec<Self> {
+        let mut m = vec![Set; mask.len()];
         for (i, o) in mask.iter().enumerate() {
             if let Unset = o {
                 m[i] = X;
@@ -183,27 +625,13 @@ impl Override {
 
 #[test]
 fn test_solution_a() {
-    let example = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
-        mem[8] = 11
-        mem[7] = 101
-        mem[8] = 0";
-    let data = example
-        .lines()
-        .map(|l| Assignment::parse(&l.trim()).expect(l))
-        .collect();
+    let data = MaskSolution::from_string(EXAMPLE_A);
     assert_eq!(MaskSolution::get_solution_a(&data).unwrap(), 165)
 }
 
 #[test]
 fn test_solution_b() {
-    let example = "mask = 000000000000000000000000000000X1001X
-        mem[42] = 100
-        mask = 00000000000000000000000000000000X0XX
-        mem[26] = 1";
-    let data = example
-        .lines()
-        .map(|l| Assignment::parse(&l.trim()).expect(l))
-        .collect();
+    let data = MaskSolution::from_string(EXAMPLE_B);
     assert_eq!(MaskSolution::get_solution_b(&data).unwrap(), 208)
 }
 
@@ -255,3 +683,149 @@ fn assert_contains_all<T: PartialEq>(m: Vec<Vec<T>>, o: Vec<Vec<T>>) {
         assert!(m.contains(&slice));
     }
 }
+
+#[test]
+fn test_floating() {
+    let mask = |s: &str| match Assignment::parse(&format!("mask = {:0>36}", s)) {
+        Some(Mask(m)) => m,
+        _ => panic!("Invalid mask: {}", s),
+    };
+    let a = Floating::new(&mask("X1001X"), 42);
+    assert_eq!(a.len(), 4);
+    assert!(a.contains(26) && a.contains(59) && !a.contains(42));
+    let b = Floating::new(&mask("X0XX"), 26);
+    let pieces = a.subtract(&b);
+    assert_eq!(pieces.iter().map(Floating::len).sum::<u128>(), 2);
+    assert!(pieces.iter().all(|p| !p.intersects(&b)));
+
+    // Far too many addresses to list out.
+    let data = vec![
+        Mask(mask(&"X".repeat(36))),
+        Mem(0, 1),
+        Mask(mask(&format!("{}0", "X".repeat(35)))),
+        Mem(0, 2),
+    ];
+    let decoder = FloatingDecoder::run(&data, WIDTH);
+    assert_eq!(decoder.len(), 1 << 36);
+    assert_eq!(decoder.mem_sum(), Some(3 << 35));
+    assert_eq!((decoder.get(6), decoder.get(7)), (Some(2), Some(1)));
+}
+
+#[test]
+fn test_floating_expand() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(48);
+    let mut data = Vec::new();
+    for _ in 0..40 {
+        let mut mask = vec![Unset; WIDTH];
+        for _ in 0..rng.gen_range(0, 6) {
+            mask[rng.gen_range(28, 36)] = X;
+        }
+        mask[rng.gen_range(28, 36)] = Set;
+        data.push(Mask(mask));
+        data.push(Mem(rng.gen_range(0, 256), rng.gen_range(0, 100)));
+    }
+    let expanded = MemDecoder::new(&data, WIDTH).run_v2();
+    let decoder = FloatingDecoder::run(&data, WIDTH);
+    assert_eq!(decoder.mem_sum(), expanded.mem_sum());
+    assert_eq!(decoder.len(), expanded.mem.len() as u128);
+    for (&address, &value) in &expanded.mem {
+        assert_eq!(decoder.get(address), Some(value));
+    }
+}
+
+#[test]
+fn test_width() {
+    let example = "mask = X1X0\nmem[8] = 3\nmem[1] = 9";
+    let parse = |width| {
+        let lines = example.lines();
+        lines
+            .map(|l| Assignment::parse_width(l, width))
+            .collect::<Option<Vec<_>>>()
+    };
+    assert!(parse(WIDTH).is_none());
+    let data = parse(4).unwrap();
+    let decoder = MemDecoder::new(&data, 4).traced().run();
+    assert_eq!(decoder.entries(), vec![(1, 12), (8, 6)]);
+    assert_eq!(decoder.dump(Radix::Decimal), "mem[1] = 12\nmem[8] = 6\n");
+    assert_eq!(decoder.dump(Radix::Hex), "mem[0x1] = 0xc\nmem[0x8] = 0x6\n");
+    assert!(decoder
+        .dump(Radix::Binary)
+        .starts_with("mem[0b0001] = 0b1100\n"));
+    let trace = decoder.trace().unwrap();
+    assert_eq!(
+        trace[1].to_string(),
+        "mem[1] = 9\n  value:  1001\n  mask:   X1X0\n  result: 1100 (12)\n"
+    );
+
+    let decoder = MemDecoder::new(&data, 4).run_v2();
+    assert_eq!(
+        decoder.entries(),
+        vec![
+            (4, 3),
+            (5, 9),
+            (6, 3),
+            (7, 9),
+            (12, 3),
+            (13, 9),
+            (14, 3),
+            (15, 9)
+        ]
+    );
+    let decoder = FloatingDecoder::run_traced(&data, 4);
+    assert_eq!(
+        decoder.trace().unwrap()[0].to_string(),
+        "mem[8] = 3\n  address: 1000\n  mask:    X1X0\n  result:  X1X0 (4 addresses)\n"
+    );
+    assert!(FloatingDecoder::run(&data, 4).trace().is_none());
+
+    // Every bit of a 64 bit word can be set.
+    let wide = format!("mask = 1{}", "X".repeat(63));
+    let data = vec![Assignment::parse_width(&wide, 64).unwrap(), Mem(0, 5)];
+    let decoder = MemDecoder::new(&data, 64).run();
+    assert_eq!(decoder.entries(), vec![(0, (1 << 63) | 5)]);
+    assert!(Assignment::parse_width(&format!("{}X", wide), 65).is_none());
+    assert!(Assignment::parse_width("mem[] = 1", 4).is_none());
+    assert!(Assignment::parse_width("mem[5] = ", 4).is_none());
+    assert!(Assignment::parse_width("mem[5] = 99999999999999999999", 4).is_none());
+}
+
+#[test]
+fn test_floating_width() {
+    let parse = |s: &str, width| Assignment::parse_width(s, width).unwrap();
+    let all = |width| parse(&format!("mask = {}", "X".repeat(width)), width);
+
+    // Every address in a 64 bit word, which is one more than a usize holds.
+    let decoder = FloatingDecoder::run(&[all(64), Mem(0, 1)], 64);
+    assert_eq!(decoder.len(), 1 << 64);
+    assert_eq!(decoder.mem_sum(), None);
+    let decoder = FloatingDecoder::run(&[all(64), Mem(0, 0)], 64);
+    assert_eq!(decoder.mem_sum(), Some(0));
+    let decoder = FloatingDecoder::run(&[all(36), Mem(0, (1 << 36) - 1)], 36);
+    assert_eq!(decoder.mem_sum(), None);
+
+    // Addresses and values are cut down to the width, like in v1.
+    let data = vec![parse("mask = 0001", 4), Mem(0x13, 0x25)];
+    let decoder = FloatingDecoder::run(&data, 4);
+    assert_eq!((decoder.get(0x3), decoder.get(0x13)), (Some(0x5), None));
+    let expanded = MemDecoder::new(&data, 4).run_v2();
+    assert_eq!(expanded.entries(), vec![(0x3, 0x5)]);
+}
+
+#[test]
+fn test_explain() {
+    let out = explain(false, None, false, WIDTH, Some("hex"), None, false).unwrap();
+    assert_eq!(
+        out,
+        "mem[0x000000007] = 0x000000065\nmem[0x000000008] = 0x000000040\nSum: 165\n"
+    );
+    let out = explain(false, None, true, WIDTH, None, Some(16), true).unwrap();
+    assert!(out.starts_with("mem[42] = 100\n"));
+    assert!(out.contains("  result:  00000000000000000000000000000001X0XX (8 addresses)\n"));
+    assert!(out.contains("\nmem[00000000000000000000000000000001X0XX] = 1\n"));
+    assert!(out.ends_with("Wrote to 10 addresses\nmem[16] = 1\nSum: 208\n"));
+    let out = explain(false, None, true, WIDTH, Some("hex"), None, false).unwrap();
+    assert!(out.starts_with("mem[00000000000000000000000000000001X0XX] = 0x000000001\n"));
+    assert!(explain(false, None, false, 4, None, None, false).is_err());
+    assert!(explain(false, None, false, WIDTH, Some("oct"), None, false).is_err());
+}
@@ -1,17 +1,19 @@
+use crate::grammar::Symbol::{Literal, Ref};
+use crate::grammar::{Grammar, Symbol};
 use crate::solution_template::Solution;
-use crate::solutions::messages::MessageRule::{Definition, Pointer};
-use crate::solutions::messages::Rule::{Either, Only};
+use crate::solutions::messages::RuleError::*;
 use lazy_static::lazy_static;
+#[cfg(test)]
+use rand::{rngs::StdRng, SeedableRng};
 use regex::Regex;
-use std::collections::BTreeMap;
 
 lazy_static! {
-    /// The pattern used by definition rules.
-    static ref DEFINITION_PATTERN: Regex = Regex::new(r#""(\w)""#).unwrap();
+    /// The pattern used to separate a rule's id from its alternatives.
+    static ref RULE_PATTERN: Regex = Regex::new(r"^\s*(\d+):(.*)$").unwrap();
 }
 
 pub struct MessageSolution {
-    rules: Vec<MessageRule>,
+    grammar: Grammar,
     messages: Vec<String>,
 }
 
@@ -31,165 +33,497 @@ impl Solution for MessageSolution {
         let rules = split.next().expect("No content.");
         let messages = split.next().expect("No messages.");
         Self {
-            rules: MessageRule::parse_all(rules),
+            grammar: MessageRule::throwing_parse_all(rules),
             messages: Self::map_lines(messages, str::to_string),
         }
     }
 
     /// Find the number of messages matching rule 0.
     fn get_solution_a(data: &Self) -> Option<usize> {
-        let pat = format!("^{}$", &data.rules[0].expand(&data.rules));
-        let regex = Regex::new(&pat).expect(&pat);
-        let matches = data.messages.iter().filter(|m| regex.is_match(m)).count();
-        Some(matches)
+        Some(data.count_matches(&data.grammar))
     }
 
     /// It turns out, these two lines are wrong. Replace them.
     /// 8: 42 | 42 8
     /// 11: 42 31 | 42 11 31
-    /// Deduction:
-    /// 8: 42+
-    /// 11: 42 (42 (42 (42 ... 31)? 31)? 31)? 31
-    /// 0: 42+ (42)n (31)n
-    /// Reference:
-    /// 42: 20 51 | 39 120
-    /// 31: 39 43 | 20 118
-    /// 0: 8 11
+    /// The grammar doesn't care that they are recursive now.
     fn get_solution_b(data: &Self) -> Option<usize> {
-        // Selected this number because it was the smallest
-        // number that stopped producing changes.
-        const HACK_DEPTH: usize = 5;
-        let r42 = data.rules[42].expand(&data.rules);
-        let r31 = data.rules[31].expand(&data.rules);
-        // Regex doesn't support recursion. Bit of a hack, here.
-        // Start with at least one 42 for 8.
-        let mut exp_0 = String::from("^(");
-        exp_0.push_str(&r42);
-        exp_0.push_str(")+");
-        // Finish with "recursive" `42(?R)31`s.
-        exp_0.push_str(&r42);
-        for _ in 0..HACK_DEPTH {
-            exp_0.push('(');
-            exp_0.push_str(&r42);
+        Some(data.count_matches(&updated(&data.grammar)))
+    }
+}
+
+/// Replaces rules 8 and 11 with the recursive ones from part B.
+fn updated(grammar: &Grammar) -> Grammar {
+    let mut grammar = grammar.clone();
+    grammar.set_rule(8, vec![vec![Ref(42)], vec![Ref(42), Ref(8)]]);
+    grammar.set_rule(
+        11,
+        vec![vec![Ref(42), Ref(31)], vec![Ref(42), Ref(11), Ref(31)]],
+    );
+    grammar
+}
+
+/// Runs one of the grammar tools on the rules from the example or the
+/// input, updated for part B if asked. Uses rule 0 unless told otherwise.
+/// - `tree` and `json` show every derivation of the message.
+/// - `ebnf` exports every rule, while `regex` exports a single one.
+/// - `enumerate` lists every message up to `limit` bytes long.
+/// - `sample` generates `limit` random messages, expanding rules at most
+///   `depth` levels deep.
+/// - `analyze` reports on every rule, or just the one asked for, counting
+///   messages up to `limit` bytes long.
+pub fn explain(
+    input: bool,
+    part_b: bool,
+    tool: &str,
+    message: Option<&str>,
+    rule: Option<usize>,
+    limit: usize,
+    depth: usize,
+) -> Result<String, String> {
+    let data = if input {
+        MessageSolution::from_file("messages.txt")
+    } else {
+        get_example()
+    };
+    let grammar = if part_b {
+        updated(&data.grammar)
+    } else {
+        data.grammar
+    };
+    let id = rule.unwrap_or(0);
+    if grammar.get_rule(id).is_none() {
+        return Err(format!("There is no rule {}.", id));
+    }
+    let mut out = String::new();
+    match tool {
+        "tree" | "json" => {
+            let message = message.ok_or("Missing the message.")?;
+            let derivations = grammar.derivations(id, message);
+            for (i, derivation) in derivations.iter().enumerate() {
+                out += &format!("Derivation {} of {}:\n", i + 1, derivations.len());
+                match tool {
+                    "json" => out += &format!("{}\n", derivation.to_json()),
+                    _ => out += &derivation.to_string(),
+                }
+            }
+            if derivations.is_empty() {
+                let matched = grammar.longest_match(id, message).unwrap_or(0);
+                out += &format!(
+                    "No derivation from rule {}, which matches at most {} of {} bytes\n",
+                    id,
+                    matched,
+                    message.len()
+                );
+            }
         }
-        for _ in 0..HACK_DEPTH {
-            exp_0.push_str(&r31);
-            exp_0.push_str(")?")
+        "ebnf" => out += &grammar.to_ebnf(),
+        "regex" => match grammar.to_regex(id) {
+            Some(regex) => out += &format!("{}\n", regex),
+            None => out += &format!("Rule {} has no regular expression\n", id),
+        },
+        "enumerate" => {
+            for message in grammar.enumerate(id, limit) {
+                out += &format!("{}\n", message);
+            }
         }
-        exp_0.push_str(&r31);
-        exp_0.push('$');
-
-        let regex = Regex::new(&exp_0).expect(&exp_0);
-        let matches = data.messages.iter().filter(|m| regex.is_match(m)).count();
-        Some(matches)
+        "sample" => {
+            let mut rng = rand::thread_rng();
+            for _ in 0..limit {
+                let message = grammar.sample(id, depth, &mut rng);
+                out += &format!("{}\n", message.ok_or("The rule derives nothing.")?);
+            }
+        }
+        "analyze" => {
+            let reports = match rule {
+                Some(rule) => vec![grammar.analyze_rule(rule, limit)],
+                None => grammar.analyze(limit),
+            };
+            for report in reports {
+                out += &format!("{}\n", report);
+            }
+        }
+        tool => return Err(format!("Unknown grammar tool: {}", tool)),
     }
+    Ok(out)
 }
 
-// Cloning due to borrow problems.
-#[derive(Debug, Clone)]
-pub enum MessageRule {
-    Definition(String),
-    Pointer(Rule<Vec<usize>>),
+impl MessageSolution {
+    /// Counts the number of messages which entirely match rule 0.
+    fn count_matches(&self, grammar: &Grammar) -> usize {
+        self.messages
+            .iter()
+            .filter(|m| grammar.is_match(0, m))
+            .count()
+    }
 }
 
-#[derive(Debug, Clone)]
-pub enum Rule<T: Clone> {
-    Either(T, T),
-    Only(T),
+/// A single rule parsed from one line of a rule file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageRule {
+    id: usize,
+    alts: Vec<Vec<Symbol>>,
 }
 
 impl MessageRule {
-    fn expand(&self, rules: &Vec<MessageRule>) -> String {
-        let ps = match self {
-            Pointer(p) => p,
-            Definition(s) => return s.clone(),
+    /// Parses a rule file into a grammar. Will panic if any errors are
+    /// encountered. Unreachable rules are allowed, as they are harmless.
+    fn throwing_parse_all(s: &str) -> Grammar {
+        let grammar = match Self::parse_all(s) {
+            Ok(grammar) => grammar,
+            Err(errors) => panic!("{}", Self::get_messages(&errors)),
         };
-        let mut exp = String::new();
-        Self::fill_recursively(&mut exp, ps, rules);
-        exp
-    }
-
-    fn fill_recursively(chars: &mut String, ps: &Rule<Vec<usize>>, rules: &Vec<MessageRule>) {
-        match ps {
-            Either(vec_a, vec_b) => {
-                chars.push('(');
-                for &idx in vec_a {
-                    match &rules[idx] {
-                        Pointer(r) => Self::fill_recursively(chars, r, rules),
-                        Definition(s) => chars.push_str(s),
-                    }
-                }
-                chars.push('|');
-                for &idx in vec_b {
-                    match &rules[idx] {
-                        Pointer(r) => Self::fill_recursively(chars, r, rules),
-                        Definition(s) => chars.push_str(s),
-                    }
+        let errors: Vec<RuleError> = Self::diagnose(&grammar, 0)
+            .into_iter()
+            .filter(|e| !e.is_warning())
+            .collect();
+        if !errors.is_empty() {
+            panic!("{}", Self::get_messages(&errors));
+        }
+        grammar
+    }
+
+    /// Parses every line in a rule file into a grammar keyed by rule id.
+    /// The order of the rules does not matter, and ids may be skipped.
+    fn parse_all(s: &str) -> Result<Grammar, Vec<RuleError>> {
+        let mut grammar = Grammar::new();
+        let mut errors = Vec::new();
+        for (ln, line) in s.lines().enumerate() {
+            match Self::parse(line, ln) {
+                Ok(rule) if grammar.get_rule(rule.id).is_some() => {
+                    errors.push(DuplicateId(ln, rule.id));
                 }
-                chars.push(')');
-            }
-            Only(vec_a) => {
-                for &idx in vec_a {
-                    match &rules[idx] {
-                        Pointer(r) => Self::fill_recursively(chars, r, rules),
-                        Definition(s) => chars.push_str(s),
-                    }
+                Ok(rule) => {
+                    grammar.set_rule(rule.id, rule.alts);
                 }
+                Err(e) => errors.push(e),
             }
         }
+        if errors.is_empty() {
+            Ok(grammar)
+        } else {
+            Err(errors)
+        }
     }
 
-    fn parse(s: &str) -> Option<(usize, Self)> {
-        let mut split = s.split(": ");
-        let idx: usize = split.next()?.parse().ok()?;
-        let txt = split.next()?;
-        if DEFINITION_PATTERN.is_match(txt) {
-            return Some((idx, Self::parse_definition(txt)?));
-        }
-        Some((idx, Self::parse_rule(txt)?))
+    /// Finds any references to undefined rules, as well as any rules
+    /// which can never be used when deriving messages from `start`.
+    fn diagnose(grammar: &Grammar, start: usize) -> Vec<RuleError> {
+        let undefined = grammar
+            .undefined_refs()
+            .into_iter()
+            .map(|(id, r)| UndefinedRef(id, r));
+        let unreachable = grammar.unreachable(start).into_iter().map(Unreachable);
+        undefined.chain(unreachable).collect()
     }
 
-    fn parse_definition(s: &str) -> Option<Self> {
-        let captures = DEFINITION_PATTERN.captures(s)?;
-        let ch = captures.get(1)?;
-        Some(Definition(ch.as_str().to_string()))
+    /// Parses a single line, e.g. `<id>: 1 2 | "ab" | 3 "c" 4`.
+    fn parse(s: &str, ln: usize) -> Result<Self, RuleError> {
+        let captures = RULE_PATTERN.captures(s).ok_or(SyntaxErr(ln))?;
+        let id = captures[1].parse().map_err(|_| SyntaxErr(ln))?;
+        let alts = Self::parse_alts(&captures[2]).ok_or(SyntaxErr(ln))?;
+        Ok(Self { id, alts })
     }
 
-    fn parse_rule(s: &str) -> Option<Self> {
-        let mut vec: Vec<Vec<usize>> = Vec::new();
-        let mut ps = s.split(" | ");
-        while let Some(p) = ps.next() {
-            let mut nums = p.split_whitespace();
-            let mut inner = Vec::new();
-            while let Some(n) = nums.next() {
-                inner.push(n.parse().ok()?);
+    /// Parses any number of alternatives, each containing any number of
+    /// rule ids and quoted literals. Empty literals and empty alternatives
+    /// are not allowed.
+    fn parse_alts(s: &str) -> Option<Vec<Vec<Symbol>>> {
+        let mut alts = vec![Vec::new()];
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                _ if c.is_whitespace() => (),
+                '|' => alts.push(Vec::new()),
+                '"' => {
+                    let mut lit = String::new();
+                    loop {
+                        match chars.next()? {
+                            '"' => break,
+                            c => lit.push(c),
+                        }
+                    }
+                    if lit.is_empty() {
+                        return None;
+                    }
+                    alts.last_mut()?.push(Literal(lit));
+                }
+                _ if c.is_ascii_digit() => {
+                    let mut buffer = String::from(c);
+                    while let Some(&peek) = chars.peek() {
+                        if !peek.is_ascii_digit() {
+                            break;
+                        }
+                        buffer.push(peek);
+                        chars.next();
+                    }
+                    alts.last_mut()?.push(Ref(buffer.parse().ok()?));
+                }
+                _ => return None,
             }
-            vec.push(inner);
         }
-        match vec.len() {
-            1 => Some(Pointer(Only(vec[0].clone()))),
-            2 => Some(Pointer(Either(vec[0].clone(), vec[1].clone()))),
-            _ => panic!("Doesn't match rules: {}", s),
+        if alts.iter().any(Vec::is_empty) {
+            return None;
         }
+        Some(alts)
+    }
+
+    fn get_messages(errors: &[RuleError]) -> String {
+        let messages: Vec<String> = errors.iter().map(RuleError::get_message).collect();
+        messages.join("\n")
+    }
+}
+
+/// Any problems encountered when reading a rule file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    /// The line at this index could not be parsed.
+    SyntaxErr(usize),
+    /// A rule was defined more than once. The second time was on this line.
+    DuplicateId(usize, usize),
+    /// A rule refers to another rule which was never defined.
+    UndefinedRef(usize, usize),
+    /// A rule can never be reached from the starting rule.
+    Unreachable(usize),
+}
+
+impl RuleError {
+    /// Whether this problem should still allow the rules to be used.
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Unreachable(_))
     }
 
-    fn parse_all(s: &str) -> Vec<Self> {
-        // Using a BTreeMap to sort by key.
-        let mut map = BTreeMap::new();
-        for line in s.lines() {
-            let (idx, rule) = Self::parse(line).expect(line);
-            map.insert(idx, rule);
+    pub fn get_message(&self) -> String {
+        match self {
+            SyntaxErr(ln) => format!("Syntax error on line #{}", ln),
+            DuplicateId(ln, id) => format!("Duplicate rule {} on line #{}", id, ln),
+            UndefinedRef(id, r) => format!("Rule {} refers to undefined rule {}", id, r),
+            Unreachable(id) => format!("Rule {} is never used", id),
         }
-        map.values().cloned().collect() // :(
     }
 }
 
 #[test]
 fn test_solution_a() {
-    // too much work today
+    let example = "0: 4 1 5
+        1: 2 3 | 3 2
+        2: 4 4 | 5 5
+        3: 4 5 | 5 4
+        4: \"a\"
+        5: \"b\"
+
+        ababbb
+        bababa
+        abbbab
+        aaabbb
+        aaaabbb";
+    let data = MessageSolution::from_string(&example.replace("        ", ""));
+    assert_eq!(MessageSolution::get_solution_a(&data).unwrap(), 2)
 }
 
 #[test]
 fn test_solution_b() {
-    // assert_eq!(None, MessageSolution::get_solution_b(&""))
+    let data = get_example();
+    assert_eq!(MessageSolution::get_solution_a(&data).unwrap(), 3);
+    assert_eq!(MessageSolution::get_solution_b(&data).unwrap(), 12)
+}
+
+#[test]
+fn test_recursive_rules() {
+    // 0: 8 11, 8: 42 | 42 8, 11: 42 31 | 42 11 31
+    let grammar = MessageRule::throwing_parse_all(
+        "0: 8 11\n8: 42 | 42 8\n11: 42 31 | 42 11 31\n42: \"a\"\n31: \"b\"",
+    );
+    assert!(grammar.is_match(0, "aab"));
+    assert!(grammar.is_match(0, "aaaaaaaabbbbbbb"));
+    assert!(!grammar.is_match(0, "aabb"));
+    assert!(!grammar.is_match(0, "aaaaaaaabbbbbbbb"));
+}
+
+#[test]
+fn test_left_recursion() {
+    let mut grammar = Grammar::new();
+    grammar.set_rule(0, vec![vec![Ref(0), Ref(1)], vec![Ref(1)]]);
+    grammar.set_rule(1, vec![vec![Literal("ab".to_string())]]);
+    assert!(grammar.is_match(0, "ababab"));
+    assert!(!grammar.is_match(0, "ababa"));
+    assert!(!grammar.is_match(0, ""));
+}
+
+#[test]
+fn test_parse_rules() {
+    let grammar = MessageRule::throwing_parse_all("7: 3 | \"xy\" | 3 \"z\" 3\n3: \"ab\"");
+    assert!(grammar.is_match(7, "ab"));
+    assert!(grammar.is_match(7, "xy"));
+    assert!(grammar.is_match(7, "abzab"));
+    assert!(!grammar.is_match(7, "abz"));
+
+    let errors = MessageRule::parse_all("0: 1 2\n1: \"a\"\n1: \"b\"\n2: 1 |\n3 \"c\"").unwrap_err();
+    assert_eq!(errors, vec![DuplicateId(2, 1), SyntaxErr(3), SyntaxErr(4)]);
+
+    let grammar = MessageRule::parse_all("0: 1 2\n1: \"a\"\n3: \"c\"").unwrap();
+    assert_eq!(
+        MessageRule::diagnose(&grammar, 0),
+        vec![UndefinedRef(0, 2), Unreachable(3)]
+    );
+}
+
+#[test]
+fn test_derivations() {
+    // 0: 1 1 | 2, 1: "a", 2: "aa"
+    let mut grammar = Grammar::new();
+    grammar.set_rule(0, vec![vec![Ref(1), Ref(1)], vec![Ref(2)]]);
+    grammar.set_rule(1, vec![vec![Literal("a".to_string())]]);
+    grammar.set_rule(2, vec![vec![Literal("aa".to_string())]]);
+    let derivations = grammar.derivations(0, "aa");
+    assert_eq!(derivations.len(), 2);
+    assert!(grammar.derivations(0, "aaa").is_empty());
+    assert_eq!(grammar.longest_match(0, "aaa"), Some(2));
+
+    let tree = "0 (alt 1) @ 0..2\n  2 (alt 0) @ 0..2\n    \"aa\" @ 0\n";
+    let json = r#"{"rule":0,"alt":1,"start":0,"end":2,"children":[{"rule":2,"alt":0,"start":0,"end":2,"children":[{"literal":"aa","start":0}]}]}"#;
+    let unambiguous = derivations.iter().find(|d| d.alt == 1).unwrap();
+    assert_eq!(unambiguous.to_string(), tree);
+    assert_eq!(unambiguous.to_json(), json);
+}
+
+fn get_example() -> MessageSolution {
+    let example = "42: 9 14 | 10 1
+        9: 14 27 | 1 26
+        10: 23 14 | 28 1
+        1: \"a\"
+        11: 42 31
+        5: 1 14 | 15 1
+        19: 14 1 | 14 14
+        12: 24 14 | 19 1
+        16: 15 1 | 14 14
+        31: 14 17 | 1 13
+        6: 14 14 | 1 14
+        2: 1 24 | 14 4
+        0: 8 11
+        13: 14 3 | 1 12
+        15: 1 | 14
+        17: 14 2 | 1 7
+        23: 25 1 | 22 14
+        28: 16 1
+        4: 1 1
+        20: 14 14 | 1 15
+        3: 5 14 | 16 1
+        27: 1 6 | 14 18
+        14: \"b\"
+        21: 14 1 | 1 14
+        25: 1 1 | 1 14
+        22: 14 14
+        8: 42
+        26: 14 22 | 1 20
+        18: 15 15
+        7: 14 5 | 1 21
+        24: 14 1
+
+        abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
+        bbabbbbaabaabba
+        babbbbaabbbbbabbbbbbaabaaabaaa
+        aaabbbbbbaaaabaababaabababbabaaabbababababaaa
+        bbbbbbbaaaabbbbaaabbabaaa
+        bbbababbbbaaaaaaaabbababaaababaabab
+        ababaaaaaabaaab
+        ababaaaaabbbaba
+        baabbaaaabbaaaababbaababb
+        abbbbabbbbaaaababbbbbbaaaababb
+        aaaaabbaabaaaaababaa
+        aaaabbaaaabbaaa
+        aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
+        babaaabbbaaabaababbaabababaaab
+        aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba";
+    MessageSolution::from_string(&example.replace("        ", ""))
+}
+
+#[test]
+fn test_generate() {
+    let rules = "0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"";
+    let grammar = MessageRule::throwing_parse_all(rules);
+    let all = grammar.enumerate(0, 6);
+    assert_eq!(all.len(), 8);
+    assert!(all.iter().all(|m| grammar.is_match(0, m)));
+    assert!(grammar.enumerate(0, 5).is_empty());
+
+    let regex = Regex::new(&format!("^{}$", grammar.to_regex(0).unwrap())).unwrap();
+    for message in &["ababbb", "bababa", "abbbab", "aaabbb", "aaaabbb"] {
+        assert_eq!(regex.is_match(message), grammar.is_match(0, message));
+    }
+    assert!(grammar
+        .to_ebnf()
+        .starts_with("r0 = r4, r1, r5 ;\nr1 = r2, r3 | r3, r2 ;\n"));
+
+    let grammar = updated(&get_example().grammar);
+    assert!(grammar.to_regex(8).is_some());
+    assert!(grammar.to_regex(0).is_none());
+
+    let mut rng = StdRng::seed_from_u64(19);
+    for _ in 0..20 {
+        let message = grammar.sample(0, 10, &mut rng).unwrap();
+        assert!(grammar.is_match(0, &message));
+    }
+}
+
+#[test]
+fn test_analysis() {
+    let grammar = updated(&get_example().grammar);
+    let reports = grammar.analyze(10);
+    let report = |id: usize| reports.iter().find(|r| r.rule == id).unwrap();
+
+    // The workaround for part B relies on these having a fixed length.
+    assert_eq!(report(42).fixed_len(), Some(5));
+    assert_eq!(report(31).fixed_len(), Some(5));
+    assert_eq!(report(42).counts.values().sum::<usize>(), 16);
+    assert!(report(42).exhaustive && report(42).ambiguous.is_none());
+
+    assert!(!report(8).finite && report(8).right_recursive && !report(8).left_recursive);
+    assert!(report(11).recursive && !report(11).right_recursive);
+    assert_eq!(report(0).min_len, Some(15));
+    assert_eq!(
+        report(8).to_string(),
+        "Rule 8: infinite, at least 5 long; {5: 16, 10: 256}; right-recursive; \
+        no ambiguity within the bound"
+    );
+
+    // 0: 0 "a" | 1, 1: "a" | "a"
+    let mut grammar = Grammar::new();
+    grammar.set_rule(
+        0,
+        vec![vec![Ref(0), Literal("a".to_string())], vec![Ref(1)]],
+    );
+    grammar.set_rule(
+        1,
+        vec![
+            vec![Literal("a".to_string())],
+            vec![Literal("a".to_string())],
+        ],
+    );
+    let report = grammar.analyze_rule(0, 3);
+    assert!(report.left_recursive && !report.finite);
+    assert_eq!(report.ambiguous, Some("a".to_string()));
+}
+
+#[test]
+fn test_explain() {
+    let out = explain(false, false, "tree", Some("bbabbbbaabaabba"), None, 0, 10);
+    assert!(out
+        .unwrap()
+        .starts_with("Derivation 1 of 1:\n0 (alt 0) @ 0..15\n"));
+    let out = explain(false, false, "json", Some("abab"), Some(42), 0, 10);
+    assert_eq!(
+        out.unwrap(),
+        "No derivation from rule 42, which matches at most 0 of 4 bytes\n"
+    );
+    let out = explain(false, true, "regex", None, None, 0, 10);
+    assert_eq!(out.unwrap(), "Rule 0 has no regular expression\n");
+    let out = explain(false, false, "sample", None, Some(42), 3, 10).unwrap();
+    assert_eq!(out.lines().count(), 3);
+    assert!(out.lines().all(|m| m.len() == 5));
+    let out = explain(false, true, "analyze", None, Some(31), 5, 10).unwrap();
+    assert!(out.starts_with("Rule 31: finite, always 5 long; {5: 16}"));
+    assert!(explain(false, false, "tree", None, None, 0, 10).is_err());
+    assert!(explain(false, false, "ebnf", None, Some(99), 0, 10).is_err());
+    assert!(explain(false, false, "parse", None, None, 0, 10).is_err());
 }
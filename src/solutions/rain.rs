@@ -1,5 +1,12 @@
+use crate::navigation::{Mode, Navigator, Route, Vector};
 use crate::solution_template::Solution;
 use crate::solutions::rain::Direction::*;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs;
+use std::slice::Iter;
+
+/// The movements from the puzzle's example.
+const EXAMPLE: &str = "F10\nN3\nF7\nR90\nF11";
 
 pub struct RainSolution;
 
@@ -28,6 +35,58 @@ impl Solution for RainSolution {
     }
 }
 
+/// Describes the ship after every movement, then its route as a whole.
+/// Also saves the route as an SVG image, if asked. Uses the example
+/// unless told otherwise.
+pub fn explain(input: bool, part_b: bool, svg: Option<&str>) -> Result<String, String> {
+    let data = if input {
+        RainSolution::from_file("rain.txt")
+    } else {
+        RainSolution::from_string(EXAMPLE)
+    };
+    let (mode, target) = if part_b {
+        (Mode::Waypoint, "waypoint")
+    } else {
+        (Mode::Heading, "facing")
+    };
+    let mut out = String::new();
+    let mut replay = Movement::replay(&data, mode);
+    for (step, state) in replay.by_ref().enumerate() {
+        out += &format!(
+            "{}: {} -> ship at {}, {} {}\n",
+            step + 1,
+            state.movement,
+            state.ship,
+            target,
+            state.target
+        );
+    }
+    let navigator = replay.finish();
+    let route = navigator.route();
+    if let Some(end) = route.ship().last() {
+        out += &format!(
+            "Sailed {:.1} to end up at {}, {} from the start\n",
+            route.distance(),
+            end,
+            end.manhattan()
+        );
+    }
+    if let Some(waypoint) = route.waypoints().last() {
+        out += &format!("Left the waypoint at {}\n", waypoint);
+    }
+    if let Some((south_west, north_east)) = route.bounds() {
+        out += &format!("Stayed between {} and {}\n", south_west, north_east);
+    }
+    if let Some(closest) = route.closest_approach() {
+        out += &format!("Came back as close as {}\n", closest);
+    }
+    if let Some(path) = svg {
+        let svg = Movement::route_svg(&data, route);
+        fs::write(path, svg).map_err(|e| format!("Can't write {}: {}", path, e))?;
+    }
+    Ok(out)
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Direction {
     North,
@@ -182,6 +241,35 @@ impl Movement {
         (ns, ew)
     }
 
+    /// Steps through the movements one at a time, recording where the
+    /// ship goes. Unlike `tally`, turns can be any number of degrees.
+    pub fn replay(movements: &[Movement], mode: Mode) -> Replay<'_> {
+        Replay {
+            movements: movements.iter(),
+            navigator: Navigator::new(mode),
+        }
+    }
+
+    /// Draws the route taken by following the movements as an SVG image,
+    /// labeling each step with its movement.
+    pub fn route_svg(movements: &[Movement], route: &Route) -> String {
+        let labels: Vec<String> = movements.iter().map(Movement::to_string).collect();
+        route.to_svg(&labels)
+    }
+
+    fn steer(&self, navigator: &mut Navigator) {
+        let amount = self.amount as f64;
+        match self.dir {
+            North => navigator.shift(Vector::new(0.0, amount)),
+            South => navigator.shift(Vector::new(0.0, -amount)),
+            East => navigator.shift(Vector::new(amount, 0.0)),
+            West => navigator.shift(Vector::new(-amount, 0.0)),
+            Left => navigator.turn(-amount),
+            Right => navigator.turn(amount),
+            Forward => navigator.forward(amount),
+        }
+    }
+
     fn translate(dir: Direction, amount: i64) -> (i64, i64) {
         match dir {
             North => (amount, 0),
@@ -193,6 +281,61 @@ impl Movement {
     }
 }
 
+/// Writes the movement as it appears in the input, e.g. `F10`.
+impl Display for Movement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let dir = match self.dir {
+            North => 'N',
+            South => 'S',
+            East => 'E',
+            West => 'W',
+            Left => 'L',
+            Right => 'R',
+            Forward => 'F',
+        };
+        write!(f, "{}{}", dir, self.amount)
+    }
+}
+
+/// Steers a ship through a list of movements, one at a time.
+pub struct Replay<'a> {
+    movements: Iter<'a, Movement>,
+    navigator: Navigator,
+}
+
+/// The ship right after a movement.
+#[derive(Copy, Clone)]
+pub struct State<'a> {
+    pub movement: &'a Movement,
+    pub ship: Vector,
+    /// The direction the ship faces, or the position of the waypoint
+    /// relative to the ship.
+    pub target: Vector,
+}
+
+impl<'a> Replay<'a> {
+    /// Follows every remaining movement, yielding the navigator along with
+    /// the whole route.
+    pub fn finish(mut self) -> Navigator {
+        self.by_ref().for_each(drop);
+        self.navigator
+    }
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = State<'a>;
+
+    fn next(&mut self) -> Option<State<'a>> {
+        let movement = self.movements.next()?;
+        movement.steer(&mut self.navigator);
+        Some(State {
+            movement,
+            ship: self.navigator.ship(),
+            target: self.navigator.target(),
+        })
+    }
+}
+
 #[test]
 fn test_solution_a() {
     let example = "F10\nN3\nF7\nR90\nF11";
@@ -216,3 +359,59 @@ fn test_rotate_waypoint() {
     assert_eq!(waypoint.right(360), waypoint);
     assert_eq!(waypoint.left(360), waypoint);
 }
+
+#[test]
+fn test_navigate() {
+    let example = "F10\nN3\nF7\nR90\nF11";
+    let data = RainSolution::from_string(example);
+    let heading = Movement::replay(&data, Mode::Heading).finish();
+    let (ns, ew) = Movement::tally(&data);
+    assert_eq!(heading.ship(), Vector::new(ew as f64, ns as f64));
+    let waypoint = Movement::replay(&data, Mode::Waypoint).finish();
+    assert_eq!(waypoint.ship().manhattan(), 286.0);
+    assert_eq!(waypoint.route().waypoints()[4], Vector::new(174.0, 28.0));
+
+    let data = RainSolution::from_string("F10\nL135\nF10");
+    let route = Movement::replay(&data, Mode::Heading)
+        .finish()
+        .route()
+        .clone();
+    assert_eq!(route.distance(), 20.0);
+    let closest = route.closest_approach().unwrap();
+    assert!((closest.length() - 50f64.sqrt()).abs() < 1e-9);
+}
+
+#[test]
+fn test_replay() {
+    let data = RainSolution::from_string(EXAMPLE);
+    let a: Vec<State> = Movement::replay(&data, Mode::Heading).collect();
+    let b: Vec<State> = Movement::replay(&data, Mode::Waypoint).collect();
+    assert_eq!(a.len(), 5);
+    assert_eq!(a[1].movement.to_string(), "N3");
+    assert_eq!(
+        (a[1].ship, b[1].ship),
+        (Vector::new(10.0, 3.0), Vector::new(100.0, 10.0))
+    );
+    assert_eq!(b[3].target, Vector::new(4.0, -10.0));
+    assert_eq!(
+        a[4].ship,
+        Movement::replay(&data, Mode::Heading).finish().ship()
+    );
+
+    let navigator = Movement::replay(&data, Mode::Waypoint).finish();
+    let svg = Movement::route_svg(&data, navigator.route());
+    assert!(svg.contains(">4: R90</text>"));
+    assert_eq!(svg.matches("<polyline").count(), 2);
+
+    let out = explain(false, true, None).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[3], "4: R90 -> ship at (170, 38), waypoint (4, -10)");
+    assert_eq!(
+        lines[5],
+        "Sailed 294.4 to end up at (214, -72), 286 from the start"
+    );
+    assert_eq!(lines[6], "Left the waypoint at (218, -82)");
+    assert_eq!(lines[7], "Stayed between (0, -72) and (214, 38)");
+    let out = explain(false, false, None).unwrap();
+    assert!(out.starts_with("1: F10 -> ship at (10, 0), facing (1, 0)\n"));
+}
@@ -1,4 +1,11 @@
+use crate::number_theory::{self, Congruence, CrtError};
 use crate::solution_template::Solution;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::TryFrom;
+
+/// The schedule from the puzzle's example.
+const EXAMPLE: &str = "939\n7,13,x,x,59,x,31,19";
 
 pub struct ShuttleSolution;
 
@@ -17,20 +24,8 @@ impl Solution for ShuttleSolution {
 
     /// Each number represents the bus' schedule.
     fn get_solution_a(data: &ShuttleData) -> Option<i64> {
-        const MAX: i32 = 1000;
-        let mut check = data.time;
-        // It should never take this long. This
-        // would just prevent an infinite loop.
-        for _ in 0..MAX {
-            for &(_, shuttle) in &data.shuttles {
-                if check % shuttle == 0 {
-                    let eta = check - data.time;
-                    return Some(eta * shuttle);
-                }
-            }
-            check += 1;
-        }
-        None
+        let next = Timetable::new(data).departures(data.time).next()?;
+        Some((next.time - data.time) * next.bus)
     }
 
     /// Each position represents the bus' schedule. In the comments,
@@ -38,7 +33,8 @@ impl Solution for ShuttleSolution {
     /// incredibly slow, though it did work. I discovered that other
     /// users on Reddit were using this "Chinese Remainder Theorem,"
     /// and copied some code from there to do the same. You can see
-    /// my lack of advanced math skills leaking through.
+    /// my lack of advanced math skills leaking through. It now uses the
+    /// general solver, which doesn't need the IDs to be coprime.
     fn get_solution_b(data: &ShuttleData) -> Option<i64> {
         // // Get an estimated range to save time.
         // let max: i64 = data.shuttles.iter()
@@ -54,12 +50,8 @@ impl Solution for ShuttleSolution {
         // }
         // None
 
-        let eta_mapped: Vec<(i64, i64)> = data
-            .shuttles
-            .iter()
-            .map(|&(i, shuttle)| (shuttle, shuttle - i))
-            .collect();
-        Some(chi_rem(&eta_mapped))
+        let earliest = Timetable::new(data).earliest(0, &[]).ok()?;
+        i64::try_from(earliest).ok()
 
         // let max: i64 = data.shuttles.iter()
         //     .map(|&(_, shuttle)| shuttle)
@@ -86,27 +78,76 @@ impl Solution for ShuttleSolution {
     }
 }
 
-fn egcd(a: i64, b: i64) -> (i64, i64, i64) {
-    if a == 0 {
-        (b, 0, 1)
+/// Lists the departures after some time, the gaps between them and when
+/// the buses all line up. `schedule` replaces the buses from the example
+/// or the input, `buses` picks some of them as in `Timetable::select` and
+/// `extra` adds constraints like `2:0` for even times. Starts from the
+/// time given by the data unless told otherwise.
+pub fn explain(
+    input: bool,
+    schedule: Option<&str>,
+    buses: Option<&str>,
+    extra: Option<&str>,
+    from: Option<i64>,
+    next: usize,
+    window: Option<i64>,
+) -> Result<String, String> {
+    let data = if input {
+        ShuttleSolution::from_file("shuttles.txt")
     } else {
-        let (g, x, y) = egcd(b % a, a);
-        (g, y - (b / a) * x, x)
+        ShuttleSolution::from_string(EXAMPLE)
+    };
+    let mut timetable = match schedule {
+        Some(s) => Timetable::parse(s).ok_or(format!("Invalid schedule: {}", s))?,
+        None => Timetable::new(&data),
+    };
+    if let Some(buses) = buses {
+        timetable = timetable.select(buses)?;
     }
-}
+    let extra = match extra {
+        Some(s) => parse_constraints(s).ok_or(format!("Invalid constraints: {}", s))?,
+        None => Vec::new(),
+    };
+    let from = from.unwrap_or(data.time);
 
-fn mod_inv(x: i64, n: i64) -> i64 {
-    let (_, x, _) = egcd(x, n);
-    (x % n + n) % n
+    let buses: Vec<String> = timetable
+        .buses()
+        .iter()
+        .map(|bus| format!("{} (+{})", bus.id, bus.offset))
+        .collect();
+    let mut out = format!("Buses: {}\n", buses.join(", "));
+    out += &format!("Next {} departures from {}:\n", next, from);
+    for departure in timetable.next_departures(from, next) {
+        out += &format!("  {}: bus {}\n", departure.time, departure.bus);
+    }
+    if let Some(window) = window {
+        out += &format!("Gaps in minutes from {} to {}:\n", from, from + window);
+        for (time, gap) in timetable.gaps(from, from + window) {
+            out += &format!("  {} to {}: {}\n", time, time + gap, gap);
+        }
+    }
+    match timetable.alignment(&extra) {
+        Ok(lined_up) => {
+            out += &format!(
+                "Lines up every {} minutes, first at {} and next at {}\n",
+                lined_up.modulus(),
+                lined_up.residue(),
+                lined_up.first_from(&(from as i128))
+            );
+        }
+        Err(e) => out += &format!("Never lines up: {}\n", e.get_message()),
+    }
+    Ok(out)
 }
 
-fn chi_rem(pairs: &[(i64, i64)]) -> i64 {
-    let product: i64 = pairs.iter().map(|&(i, _)| i).product();
-
-    pairs.iter().fold(0, |acc, &(i, j)| {
-        let p = product / i;
-        acc + j * mod_inv(p, i) * p
-    }) % product
+/// Reads constraints like `2:0,3:1` as `modulus:residue`.
+fn parse_constraints(s: &str) -> Option<Vec<Congruence<i128>>> {
+    s.split(',')
+        .map(|c| {
+            let (modulus, residue) = c.split_once(':')?;
+            Congruence::new(residue.trim().parse().ok()?, modulus.trim().parse().ok()?)
+        })
+        .collect()
 }
 
 // fn first_mod_over(num: i64, of: i64) -> i64 {
@@ -184,6 +225,8 @@ impl ShuttleData {
         })
     }
 
+    /// Reads each bus ID along with its position in the schedule,
+    /// skipping any `x` placeholders.
     fn read_shuttles(s: &str) -> Vec<(i64, i64)> {
         let mut vec = Vec::new();
         for (i, shuttle) in s.split(",").enumerate() {
@@ -199,10 +242,199 @@ impl ShuttleData {
     }
 }
 
+/// A bus which leaves at every multiple of its ID, starting from 0.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bus {
+    pub id: i64,
+    /// How long after the first bus this one should leave.
+    pub offset: i64,
+}
+
+/// Every bus in a schedule, along with its position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timetable {
+    buses: Vec<Bus>,
+}
+
+impl Timetable {
+    pub fn new(data: &ShuttleData) -> Self {
+        let buses = data.shuttles.iter().map(|&(offset, id)| Bus { id, offset });
+        Self {
+            buses: buses.collect(),
+        }
+    }
+
+    /// Reads a schedule like `7,13,x,x,59`, where each bus is offset by
+    /// its position. Yields `None` if any ID isn't a positive number.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut buses = Vec::new();
+        for (i, id) in s.trim().split(',').enumerate() {
+            if id == "x" {
+                continue;
+            }
+            let id: i64 = id.trim().parse().ok().filter(|&id| id > 0)?;
+            buses.push(Bus {
+                id,
+                offset: i as i64,
+            });
+        }
+        Some(Self { buses })
+    }
+
+    pub fn buses(&self) -> &[Bus] {
+        &self.buses
+    }
+
+    /// Keeps only the given buses, at their original offsets.
+    pub fn only(&self, ids: &[i64]) -> Self {
+        let buses = self.buses.iter().filter(|bus| ids.contains(&bus.id));
+        Self {
+            buses: buses.copied().collect(),
+        }
+    }
+
+    /// Picks buses from a list like `7,13`, keeping their offsets, or like
+    /// `7:0,13:2`, moving each to a new offset.
+    pub fn select(&self, s: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid buses: {}", s);
+        if !s.contains(':') {
+            let ids = s.split(',').map(|id| id.trim().parse().ok());
+            return Ok(self.only(&ids.collect::<Option<Vec<_>>>().ok_or_else(invalid)?));
+        }
+        let buses = s.split(',').map(|bus| {
+            let (id, offset) = bus.split_once(':')?;
+            Some(Bus {
+                id: id.trim().parse().ok()?,
+                offset: offset.trim().parse().ok()?,
+            })
+        });
+        let buses = buses.collect::<Option<Vec<_>>>().ok_or_else(invalid)?;
+        self.at_offsets(&buses)
+            .ok_or(format!("Not every bus is in the timetable: {}", s))
+    }
+
+    /// Keeps only the given buses, each leaving at its new offset. Yields
+    /// `None` if any of them isn't in the timetable.
+    pub fn at_offsets(&self, buses: &[Bus]) -> Option<Self> {
+        let known = |bus: &Bus| self.buses.iter().any(|b| b.id == bus.id);
+        if !buses.iter().all(known) {
+            return None;
+        }
+        Some(Self {
+            buses: buses.to_vec(),
+        })
+    }
+
+    /// Every departure at or after `from`, in order. Buses leaving at the
+    /// same time are listed in schedule order.
+    pub fn departures(&self, from: i64) -> Departures {
+        let queue = self.buses.iter().enumerate().map(|(i, bus)| {
+            let next = Congruence::new(0, bus.id).expect("Bus IDs must be positive.");
+            Reverse((next.first_from(&from), i, bus.id))
+        });
+        Departures {
+            queue: queue.collect(),
+        }
+    }
+
+    /// The next `n` departures at or after `from`.
+    pub fn next_departures(&self, from: i64, n: usize) -> Vec<Departure> {
+        self.departures(from).take(n).collect()
+    }
+
+    /// The time between each departure in `from..=to` and the next one in
+    /// the window, as `(time, gap)`. Buses leaving together count once.
+    pub fn gaps(&self, from: i64, to: i64) -> Vec<(i64, i64)> {
+        let mut times: Vec<i64> = self
+            .departures(from)
+            .map(|d| d.time)
+            .take_while(|&t| t <= to)
+            .collect();
+        times.dedup();
+        times.windows(2).map(|w| (w[0], w[1] - w[0])).collect()
+    }
+
+    /// Every time when each bus leaves at its offset and every extra
+    /// constraint holds.
+    pub fn alignment(
+        &self,
+        extra: &[Congruence<i128>],
+    ) -> Result<Congruence<i128>, AlignmentError> {
+        let buses = self.buses.iter().map(|bus| {
+            Congruence::new(-bus.offset as i128, bus.id as i128).expect("Bus IDs must be positive.")
+        });
+        number_theory::solve(buses.chain(extra.iter().cloned())).map_err(|e| {
+            let (i, overflow) = match e {
+                CrtError::Inconsistent(i) => (i, false),
+                CrtError::Overflow(i) => (i, true),
+            };
+            match self.buses.get(i) {
+                Some(&bus) => AlignmentError::Bus(bus, overflow),
+                None => AlignmentError::Extra(i - self.buses.len(), overflow),
+            }
+        })
+    }
+
+    /// The earliest time at or after `from` when every bus leaves at its
+    /// offset, which must also satisfy every extra constraint.
+    pub fn earliest(&self, from: i64, extra: &[Congruence<i128>]) -> Result<i128, AlignmentError> {
+        Ok(self.alignment(extra)?.first_from(&(from as i128)))
+    }
+}
+
+/// A reason why the buses never line up. Each points at the first bus or
+/// extra constraint which can't hold along with the ones before it, and
+/// says whether that's because the numbers got too big.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlignmentError {
+    Bus(Bus, bool),
+    /// The extra constraint at this index.
+    Extra(usize, bool),
+}
+
+impl AlignmentError {
+    pub fn get_message(&self) -> String {
+        let (culprit, overflow) = match self {
+            AlignmentError::Bus(bus, overflow) => {
+                (format!("bus {} at +{}", bus.id, bus.offset), overflow)
+            }
+            AlignmentError::Extra(i, overflow) => (format!("extra constraint #{}", i), overflow),
+        };
+        if *overflow {
+            format!("{} overflowed", culprit)
+        } else {
+            format!("{} can't line up with the rest", culprit)
+        }
+    }
+}
+
+/// A bus leaving at some time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Departure {
+    pub time: i64,
+    pub bus: i64,
+}
+
+/// Every departure from a timetable, in order. Never ends unless the
+/// timetable is empty.
+pub struct Departures {
+    /// The next departure of each bus as `(time, position, id)`.
+    queue: BinaryHeap<Reverse<(i64, usize, i64)>>,
+}
+
+impl Iterator for Departures {
+    type Item = Departure;
+
+    fn next(&mut self) -> Option<Departure> {
+        let Reverse((time, i, bus)) = self.queue.pop()?;
+        self.queue.push(Reverse((time + bus, i, bus)));
+        Some(Departure { time, bus })
+    }
+}
+
 #[test]
 fn test_solution_a() {
-    let example = "939\n7,13,x,x,59,x,31,19";
-    let data = ShuttleSolution::from_string(example);
+    let data = ShuttleSolution::from_string(EXAMPLE);
     assert_eq!(ShuttleSolution::get_solution_a(&data).unwrap(), 295)
 }
 
@@ -213,12 +445,16 @@ fn test_solution_a() {
 // 1789,37,47,1889 first occurs at timestamp 1202161486.
 #[test]
 fn test_solution_b() {
-    check_solution_b("939\n7,13,x,x,59,x,31,19", 1068781);
+    check_solution_b(EXAMPLE, 1068781);
     check_solution_b("0\n17,x,13,19", 3417);
     check_solution_b("0\n67,7,59,61", 754018);
     check_solution_b("0\n67,x,7,59,61", 779210);
     check_solution_b("0\n67,7,x,59,61", 1261476);
     check_solution_b("0\n1789,37,47,1889", 1202161486);
+    // These IDs aren't coprime, but still line up.
+    check_solution_b("0\n6,x,x,x,10", 6);
+    let data = ShuttleSolution::from_string("0\n6,10");
+    assert_eq!(ShuttleSolution::get_solution_b(&data), None);
 }
 
 #[cfg(test)]
@@ -226,3 +462,64 @@ fn check_solution_b(example: &str, expected: i64) {
     let data = ShuttleSolution::from_string(example);
     assert_eq!(ShuttleSolution::get_solution_b(&data).unwrap(), expected);
 }
+
+#[test]
+fn test_timetable() {
+    let timetable = Timetable::parse("7,13,x,x,59,x,31,19").unwrap();
+    let next: Vec<(i64, i64)> = timetable
+        .next_departures(939, 4)
+        .iter()
+        .map(|d| (d.time, d.bus))
+        .collect();
+    assert_eq!(next, vec![(944, 59), (945, 7), (949, 13), (950, 19)]);
+    assert_eq!(timetable.gaps(0, 14), vec![(0, 7), (7, 6), (13, 1)]);
+    assert_eq!(timetable.earliest(0, &[]), Ok(1068781));
+
+    // 7 and 13 line up every 91 minutes.
+    let pair = timetable.only(&[7, 13]);
+    assert_eq!(pair.earliest(0, &[]), Ok(77));
+    assert_eq!(pair.earliest(78, &[]), Ok(168));
+    let extra = [
+        Congruence::new(0, 2).unwrap(),
+        Congruence::new(1, 2).unwrap(),
+    ];
+    assert_eq!(pair.earliest(0, &extra[..1]), Ok(168));
+    assert_eq!(
+        pair.earliest(0, &extra),
+        Err(AlignmentError::Extra(1, false))
+    );
+    // 13 can leave two minutes after 7 instead of one.
+    let bus = |id, offset| Bus { id, offset };
+    let moved = timetable.at_offsets(&[bus(7, 0), bus(13, 2)]).unwrap();
+    assert_eq!(moved.earliest(0, &[]), Ok(63));
+    assert_eq!(moved.earliest(64, &[]), Ok(154));
+    assert!(timetable.at_offsets(&[bus(7, 0), bus(11, 1)]).is_none());
+    let clash = timetable.at_offsets(&[bus(7, 0), bus(13, 0), bus(7, 1)]);
+    assert_eq!(
+        clash.unwrap().earliest(0, &[]),
+        Err(AlignmentError::Bus(bus(7, 1), false))
+    );
+    assert!(Timetable::parse("7,0").is_none());
+    assert!(Timetable::parse("7,y").is_none());
+    assert_eq!(timetable.select("7, 13"), Ok(pair));
+    assert_eq!(timetable.select("7:0,13:2"), Ok(moved));
+    assert!(timetable.select("7:0,13").is_err());
+    assert!(timetable.select("7:0,11:1").is_err());
+}
+
+#[test]
+fn test_explain() {
+    let out = explain(false, None, Some("7,13"), Some("2:0"), None, 2, Some(10)).unwrap();
+    let expected = "Buses: 7 (+0), 13 (+1)
+Next 2 departures from 939:
+  945: bus 7
+  949: bus 13
+Gaps in minutes from 939 to 949:
+  945 to 949: 4
+Lines up every 182 minutes, first at 168 and next at 1078
+";
+    assert_eq!(out, expected);
+    let out = explain(false, Some("6,10"), None, None, Some(0), 0, None).unwrap();
+    assert!(out.ends_with("Never lines up: bus 10 at +1 can't line up with the rest\n"));
+    assert!(explain(false, None, None, Some("0:1"), None, 1, None).is_err());
+}
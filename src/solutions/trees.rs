@@ -1,50 +1,167 @@
+use crate::grid::Grid;
 use crate::solution_template::Solution;
+use std::collections::HashSet;
 
 pub struct TreeSolution;
 
 /// Day 3: count the number of trees in a matrix according to a given slope.
 impl Solution for TreeSolution {
-    type Data = Vec<Vec<bool>>;
+    type Data = Grid<bool>;
     type Output = i64;
 
     const MESSAGE_A: &'static str = "Number of trees";
     const MESSAGE_B: &'static str = "Product of rows";
 
-    fn from_string(s: &str) -> Vec<Vec<bool>> {
+    fn from_string(s: &str) -> Grid<bool> {
         Self::map_chars(s, is_tree)
     }
 
     /// Counts the number of trees encountered travelling a distance of three per row.
-    fn get_solution_a(data: &Vec<Vec<bool>>) -> Option<i64> {
-        Some(count_trees(data, 3, 1))
+    fn get_solution_a(data: &Grid<bool>) -> Option<i64> {
+        count_trees(data, 3, 1)
     }
 
     /// Multiplies the number of trees found in a predefined list of rows.
-    fn get_solution_b(data: &Vec<Vec<bool>>) -> Option<i64> {
-        let a = count_trees(data, 1, 1);
-        let b = count_trees(data, 3, 1);
-        let c = count_trees(data, 5, 1);
-        let d = count_trees(data, 7, 1);
-        let e = count_trees(data, 1, 2);
+    fn get_solution_b(data: &Grid<bool>) -> Option<i64> {
+        let a = count_trees(data, 1, 1)?;
+        let b = count_trees(data, 3, 1)?;
+        let c = count_trees(data, 5, 1)?;
+        let d = count_trees(data, 7, 1)?;
+        let e = count_trees(data, 1, 2)?;
         println!("a: {}, b: {}, c: {}, d: {}, e: {}", a, b, c, d, e);
         Some(a * b * c * d * e)
     }
 }
 
-/// Counts the number of trees encountered according to a slope.
-fn count_trees(matrix: &Vec<Vec<bool>>, over: usize, down: usize) -> i64 {
-    let mut count = 0;
-    let mut x = 0;
-    let mut y = 0;
-    while y < matrix.len() {
-        let vec = &matrix[y];
-        if vec[x % vec.len()] {
-            count += 1;
+/// Counts the number of trees encountered according to a slope. Yields
+/// `None` if the slope never moves down.
+fn count_trees(matrix: &Grid<bool>, over: usize, down: usize) -> Option<i64> {
+    let hits = route(matrix, over, down)?.filter(|&p| is_hit(matrix, p));
+    Some(hits.count() as i64)
+}
+
+/// Every position visited on the way down as `(x, y)`. The map repeats
+/// to the right, so `x` keeps counting past its edge. Yields `None` if
+/// the slope never moves down, since it would never reach the bottom.
+fn route(
+    matrix: &Grid<bool>,
+    over: usize,
+    down: usize,
+) -> Option<impl Iterator<Item = (usize, usize)>> {
+    if down == 0 {
+        return None;
+    }
+    let rows = (0..matrix.height()).step_by(down);
+    Some(rows.enumerate().map(move |(i, y)| (i * over, y)))
+}
+
+fn is_hit(matrix: &Grid<bool>, (x, y): (usize, usize)) -> bool {
+    *matrix.get_wrapping(x as isize, y as isize)
+}
+
+/// Tries every slope up to `max` as `(over, down)`, listing the ones
+/// which hit the fewest and the most trees. Also draws the route of the
+/// slope to `show`, if there is one. Uses the example unless told
+/// otherwise.
+pub fn survey(
+    input: bool,
+    max: (usize, usize),
+    show: Option<(usize, usize)>,
+) -> Result<String, String> {
+    let matrix = if input {
+        TreeSolution::from_file("trees.txt")
+    } else {
+        get_example()
+    };
+    let survey = Survey::new(&matrix, max.0, max.1);
+    let mut out = String::new();
+    if let Some((over, down)) = show {
+        out += &render_route(&matrix, over, down).ok_or("The slope needs to move down.")?;
+    }
+    let trees = |slope: Option<&(usize, usize)>| {
+        slope.and_then(|&(over, down)| count_trees(&matrix, over, down))
+    };
+    let fewest = survey.fewest();
+    let most = survey.most();
+    out += &format!("Fewest trees ({:?}): {:?}\n", trees(fewest.first()), fewest);
+    out += &format!("Most trees ({:?}): {:?}\n", trees(most.first()), most);
+    Ok(out)
+}
+
+/// Draws the route over as many copies of the map as it needs. Trees on
+/// the route are drawn as `X` and open squares on the route as `O`.
+/// Yields `None` if the slope never moves down.
+pub fn render_route(matrix: &Grid<bool>, over: usize, down: usize) -> Option<String> {
+    let visited: HashSet<(usize, usize)> = route(matrix, over, down)?.collect();
+    let width = visited.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+    let copies = width.div_ceil(matrix.width().max(1)).max(1);
+    let mut out = String::new();
+    for y in 0..matrix.height() {
+        for x in 0..copies * matrix.width() {
+            let tree = is_hit(matrix, (x, y));
+            out.push(match (visited.contains(&(x, y)), tree) {
+                (true, true) => 'X',
+                (true, false) => 'O',
+                (false, true) => '#',
+                (false, false) => '.',
+            });
         }
-        x += over;
-        y += down;
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// The number of trees hit on every slope within a range.
+#[derive(Debug, Clone)]
+pub struct Survey {
+    /// Each slope as `(over, down)`, with the number of trees hit.
+    pub results: Vec<((usize, usize), i64)>,
+}
+
+impl Survey {
+    /// Tries every slope moving up to `max_over` to the right and up to
+    /// `max_down` down each step. Slopes are only tried in their lowest
+    /// terms, e.g. `(2, 2)` would only visit some of the squares visited
+    /// by `(1, 1)`, so it is skipped.
+    pub fn new(matrix: &Grid<bool>, max_over: usize, max_down: usize) -> Self {
+        let mut results = Vec::new();
+        for down in 1..=max_down {
+            for over in 0..=max_over {
+                if gcd(over, down) != 1 {
+                    continue;
+                }
+                if let Some(trees) = count_trees(matrix, over, down) {
+                    results.push(((over, down), trees));
+                }
+            }
+        }
+        Self { results }
+    }
+
+    /// Every slope which hit the fewest trees.
+    pub fn fewest(&self) -> Vec<(usize, usize)> {
+        let min = self.results.iter().map(|&(_, n)| n).min();
+        self.with_count(min)
+    }
+
+    /// Every slope which hit the most trees.
+    pub fn most(&self) -> Vec<(usize, usize)> {
+        let max = self.results.iter().map(|&(_, n)| n).max();
+        self.with_count(max)
+    }
+
+    fn with_count(&self, count: Option<i64>) -> Vec<(usize, usize)> {
+        let matching = self.results.iter().filter(|&&(_, n)| Some(n) == count);
+        matching.map(|&(slope, _)| slope).collect()
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
-    count
 }
 
 /// Determines whether a given character represents a tree.
@@ -80,7 +197,67 @@ fn test_count_trees() {
     let matrix1 = TreeSolution::from_string(trees1);
     let matrix2 = TreeSolution::from_string(trees2);
     let matrix3 = TreeSolution::from_string(trees3);
-    assert_eq!(count_trees(&matrix1, 3, 1), 2);
-    assert_eq!(count_trees(&matrix2, 3, 1), 5);
-    assert_eq!(count_trees(&matrix3, 1, 2), 3);
+    assert_eq!(count_trees(&matrix1, 3, 1), Some(2));
+    assert_eq!(count_trees(&matrix2, 3, 1), Some(5));
+    assert_eq!(count_trees(&matrix3, 1, 2), Some(3));
+    assert_eq!(count_trees(&matrix3, 1, 0), None);
+}
+
+#[test]
+fn test_survey() {
+    let matrix = get_example();
+    let survey = Survey::new(&matrix, 7, 2);
+    let results = &survey.results;
+    for &(slope, trees) in &[
+        ((1, 1), 2),
+        ((3, 1), 7),
+        ((5, 1), 3),
+        ((7, 1), 4),
+        ((1, 2), 2),
+    ] {
+        assert!(results.contains(&(slope, trees)));
+    }
+    assert!(results
+        .iter()
+        .all(|&((over, down), _)| gcd(over, down) == 1));
+    let fewest = survey.fewest();
+    let most = survey.most();
+    assert!(!fewest.is_empty() && !most.is_empty());
+    let count = |slope: (usize, usize)| count_trees(&matrix, slope.0, slope.1).unwrap();
+    assert!(results
+        .iter()
+        .all(|&(_, n)| count(fewest[0]) <= n && n <= count(most[0])));
+}
+
+#[test]
+fn test_render_route() {
+    let matrix = get_example();
+    assert_eq!(render_route(&matrix, 3, 0), None);
+    let route = render_route(&matrix, 3, 1).unwrap();
+    let lines: Vec<&str> = route.lines().collect();
+    assert_eq!(lines.len(), 11);
+    assert_eq!(lines[0].len(), 33);
+    assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+    assert_eq!(route.matches('X').count(), 7);
+    assert_eq!(route.matches('O').count(), 4);
+
+    let out = survey(false, (3, 1), Some((3, 1))).unwrap();
+    assert!(out.starts_with(&route));
+    assert!(out.ends_with("Most trees (Some(7)): [(3, 1)]\n"));
+    assert!(survey(false, (3, 1), Some((3, 0))).is_err());
+}
+
+fn get_example() -> Grid<bool> {
+    let example = "..##.......
+        #...#...#..
+        .#....#..#.
+        ..#.#...#.#
+        .#...##..#.
+        ..#.##.....
+        .#.#.#....#
+        .#........#
+        #.##...#...
+        #...##....#
+        .#..#...#.#";
+    TreeSolution::from_string(&example.replace(" ", ""))
 }
@@ -0,0 +1,181 @@
+use crate::grid::Grid;
+use std::ops::Index;
+
+/// One of the eight ways a grid can be rotated and reflected, i.e. an
+/// element of the dihedral group D4. The grid is first mirrored from
+/// left to right, if `flipped`, and then rotated clockwise by 90 degrees
+/// `rotations` times. Grids do not need to be square.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Orientation {
+    rotations: u8,
+    flipped: bool,
+}
+
+impl Orientation {
+    /// Leaves the grid exactly as it is.
+    pub const IDENTITY: Self = Self::new(0, false);
+
+    /// Constructs an orientation from any number of clockwise rotations.
+    pub const fn new(rotations: u8, flipped: bool) -> Self {
+        Self {
+            rotations: rotations % 4,
+            flipped,
+        }
+    }
+
+    /// Generates all eight orientations, starting with the identity.
+    pub fn all() -> [Self; 8] {
+        let mut all = [Self::IDENTITY; 8];
+        for (i, o) in all.iter_mut().enumerate() {
+            *o = Self::new(i as u8 % 4, i >= 4);
+        }
+        all
+    }
+
+    /// The orientation produced by applying `self` and then `other`.
+    /// Nothing in the solutions needs to compose orientations yet.
+    #[allow(dead_code)]
+    pub fn then(self, other: Self) -> Self {
+        // Mirroring a grid reverses the direction of any earlier rotations.
+        if other.flipped {
+            Self::new(4 + other.rotations - self.rotations, !self.flipped)
+        } else {
+            Self::new(self.rotations + other.rotations, self.flipped)
+        }
+    }
+
+    /// The orientation which undoes this one.
+    pub fn inverse(self) -> Self {
+        if self.flipped {
+            self
+        } else {
+            Self::new(4 - self.rotations, false)
+        }
+    }
+
+    /// The dimensions of a `width` x `height` grid after being transformed.
+    pub fn dims(self, width: usize, height: usize) -> (usize, usize) {
+        if self.rotations.is_multiple_of(2) {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+
+    /// Finds where the point `(x, y)` in a `width` x `height` grid ends up.
+    pub fn map(self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        let (mut x, mut y, mut w, mut h) = (x, y, width, height);
+        if self.flipped {
+            x = w - 1 - x;
+        }
+        for _ in 0..self.rotations {
+            let rotated = (h - 1 - y, x);
+            x = rotated.0;
+            y = rotated.1;
+            std::mem::swap(&mut w, &mut h);
+        }
+        (x, y)
+    }
+
+    /// Generates a transformed copy of the grid.
+    pub fn apply<T: Clone>(self, grid: &Grid<T>) -> Grid<T> {
+        self.view(grid).to_grid()
+    }
+
+    /// Generates a transformed view of the grid without copying it.
+    pub fn view<T>(self, grid: &Grid<T>) -> View<'_, T> {
+        View::new(grid, self)
+    }
+}
+
+/// A rotated and reflected window into a rectangular grid.
+#[derive(Debug, Copy, Clone)]
+pub struct View<'a, T> {
+    grid: &'a Grid<T>,
+    /// The orientation which maps points in this view back to the grid.
+    inverse: Orientation,
+    width: usize,
+    height: usize,
+}
+
+impl<'a, T> View<'a, T> {
+    fn new(grid: &'a Grid<T>, orientation: Orientation) -> Self {
+        let (width, height) = orientation.dims(grid.width(), grid.height());
+        Self {
+            grid,
+            inverse: orientation.inverse(),
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Retrieves the value at `(x, y)` in this view, if it is in bounds.
+    pub fn get(&self, x: usize, y: usize) -> Option<&'a T> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let (sx, sy) = self.inverse.map(x, y, self.width, self.height);
+        self.grid.get(sx, sy)
+    }
+
+    /// Copies every value in this view into a new grid.
+    pub fn to_grid(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::from_fn(self.width, self.height, |x, y| self[(x, y)].clone())
+    }
+}
+
+/// Retrieves the value at `(x, y)` in this view. Panics when out of bounds.
+impl<'a, T> Index<(usize, usize)> for View<'a, T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).expect("Index out of bounds.")
+    }
+}
+
+#[test]
+fn test_group() {
+    let all = Orientation::all();
+    for &a in &all {
+        assert_eq!(a.then(a.inverse()), Orientation::IDENTITY);
+        assert_eq!(a.inverse().then(a), Orientation::IDENTITY);
+        for &b in &all {
+            for &c in &all {
+                assert_eq!(a.then(b).then(c), a.then(b.then(c)));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_apply() {
+    let grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+    let rotate = Orientation::new(1, false);
+    let flip = Orientation::new(0, true);
+    assert_eq!(
+        rotate.apply(&grid).to_rows(),
+        vec![vec![4, 1], vec![5, 2], vec![6, 3]]
+    );
+    assert_eq!(
+        flip.apply(&grid).to_rows(),
+        vec![vec![3, 2, 1], vec![6, 5, 4]]
+    );
+    for &a in &Orientation::all() {
+        for &b in &Orientation::all() {
+            let twice = b.apply(&a.apply(&grid));
+            assert_eq!(twice, a.then(b).apply(&grid));
+        }
+        assert_eq!(a.inverse().apply(&a.apply(&grid)), grid);
+    }
+}
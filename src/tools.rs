@@ -0,0 +1,374 @@
+use crate::animate::Options;
+use crate::solutions::conway::{self, Rule};
+use crate::solutions::{ferry, game, jigsaw, masks, messages, rain, shuttles, trees};
+use std::collections::{HashMap, HashSet};
+use std::process;
+use std::str::FromStr;
+
+/// Runs from the command line with the arguments following its flag.
+/// Yields the text to print, or a reason why it couldn't run.
+type Tool = fn(&[String]) -> Result<String, String>;
+
+/// Every tool as `(flag, arguments, tool)`.
+const TOOLS: &[(&str, &str, Tool)] = &[
+    (
+        "--animate",
+        "<ferry|conway> [--part <a|b>] [--fps <n>] [--cycles <n>] [--slice <z,w,...>] [--input]",
+        animate,
+    ),
+    ("--bench", "<ferry|game>", bench),
+    (
+        "--export",
+        "<jigsaw|ferry|conway> <path.pbm|pgm|ppm|svg> [--part <a|b>] [--scale <n>] \
+[--cycles <n>] [--slice <z,w,...>] [--pattern <path>] [--input]",
+        export,
+    ),
+    (
+        "--grammar",
+        "<tree|json|ebnf|regex|enumerate|sample|analyze> [<message>] [--rule <id>] \
+[--limit <n>] [--depth <n>] [--part <a|b>] [--input]",
+        grammar,
+    ),
+    (
+        "--life",
+        "[--rule <B3/S23>] [--neighborhood <moore|von-neumann>] [--radius <n>] [--dims <n>] \
+[--cycles <n>] [--slice <z,w,...>] [--input]",
+        life,
+    ),
+    (
+        "--memory",
+        "[<path>] [--part <a|b>] [--width <n>] [--radix <dec|hex|bin>] [--address <n>] \
+[--trace] [--input]",
+        memory,
+    ),
+    ("--route", "[--part <a|b>] [--svg <path>] [--input]", route),
+    ("--search", "[--pattern <path>]", search),
+    (
+        "--seats",
+        "[--part <a|b>] [--neighbors <adjacent|orthogonal|sight|x,y;...>] [--limit <n>] \
+[--seat <x,y>] [--input]",
+        seats,
+    ),
+    (
+        "--slopes",
+        "[--max <over,down>] [--show <over,down>] [--input]",
+        slopes,
+    ),
+    (
+        "--timetable",
+        "[--schedule <7,13,x,...>] [--buses <id,...|id:offset,...>] [--extra <modulus:residue,...>] \
+[--from <time>] [--next <n>] [--window <minutes>] [--input]",
+        timetable,
+    ),
+];
+
+/// Runs the tool named by the first argument, yielding `false` if there
+/// are no arguments. Prints the usage and exits if anything is wrong.
+pub fn run(args: &[String]) -> bool {
+    let flag = match args.first() {
+        Some(flag) => flag,
+        None => return false,
+    };
+    let result = match TOOLS.iter().find(|(f, _, _)| f == flag) {
+        Some((_, _, tool)) => tool(&args[1..]),
+        None => Err(format!("Unknown option: {}", flag)),
+    };
+    match result {
+        Ok(out) => print!("{}", out),
+        Err(e) => {
+            eprintln!("{}\n{}", e, usage());
+            process::exit(-1);
+        }
+    }
+    true
+}
+
+fn usage() -> String {
+    let lines: Vec<String> = TOOLS
+        .iter()
+        .map(|(flag, args, _)| format!("  {} {}", flag, args))
+        .collect();
+    format!("Usage:\n{}", lines.join("\n"))
+}
+
+/// The arguments given to a tool, split into values, switches such as
+/// `--input` and options such as `--part b`.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    values: Vec<String>,
+    switches: HashSet<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    /// Sorts the arguments into values, the given `switches` and the
+    /// given `options`. Anything else starting with `--` is an error.
+    pub fn parse(args: &[String], switches: &[&str], options: &[&str]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if switches.contains(&arg.as_str()) {
+                parsed.switches.insert(arg.clone());
+            } else if options.contains(&arg.as_str()) {
+                let value = args.next().ok_or(format!("Missing a value for {}.", arg))?;
+                parsed.options.insert(arg.clone(), value.clone());
+            } else if arg.starts_with("--") {
+                return Err(format!("Unknown option: {}", arg));
+            } else {
+                parsed.values.push(arg.clone());
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// The value at `index`, which is described as `name` if missing.
+    pub fn value(&self, index: usize, name: &str) -> Result<&str, String> {
+        match self.values.get(index) {
+            Some(value) => Ok(value),
+            None => Err(format!("Missing the {}.", name)),
+        }
+    }
+
+    pub fn has(&self, switch: &str) -> bool {
+        self.switches.contains(switch)
+    }
+
+    /// The value of an option, if it was given.
+    pub fn get_str(&self, option: &str) -> Option<&str> {
+        self.options.get(option).map(String::as_str)
+    }
+
+    /// Parses an option, if it was given.
+    pub fn get<T: FromStr>(&self, option: &str) -> Result<Option<T>, String> {
+        match self.options.get(option) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid value for {}: {}", option, value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses a comma separated option, if it was given.
+    pub fn get_list<T: FromStr>(&self, option: &str) -> Result<Option<Vec<T>>, String> {
+        match self.options.get(option) {
+            Some(value) => value
+                .split(',')
+                .map(|s| s.trim().parse())
+                .collect::<Result<_, _>>()
+                .map(Some)
+                .map_err(|_| format!("Invalid value for {}: {}", option, value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses an option made of two comma separated values, if it was
+    /// given.
+    pub fn get_pair<T: FromStr>(&self, option: &str) -> Result<Option<(T, T)>, String> {
+        match self.get_list(option)? {
+            Some(mut list) if list.len() == 2 => {
+                let second = list.pop().unwrap();
+                Ok(Some((list.pop().unwrap(), second)))
+            }
+            Some(_) => Err(format!("{} needs two values.", option)),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether `--part b` was given. Defaults to part a.
+    pub fn part_b(&self) -> Result<bool, String> {
+        match self.get_str("--part") {
+            None | Some("a") => Ok(false),
+            Some("b") => Ok(true),
+            Some(part) => Err(format!("Unknown part: {}", part)),
+        }
+    }
+}
+
+/// Plays one of the grid simulations in the terminal.
+fn animate(args: &[String]) -> Result<String, String> {
+    let options = Options::parse(args)?;
+    match options.day.as_str() {
+        "ferry" => ferry::animate(&options)?,
+        "conway" => conway::animate(&options)?,
+        day => return Err(format!("{} can't be animated.", day)),
+    }
+    Ok(String::new())
+}
+
+/// Times one of the slower days.
+fn bench(args: &[String]) -> Result<String, String> {
+    match args.first().map(String::as_str) {
+        Some("ferry") => ferry::bench(),
+        Some("game") => game::bench(),
+        Some(day) => Err(format!("{} has no benchmark.", day)),
+        None => Err("Missing the day to benchmark.".to_string()),
+    }
+}
+
+/// Saves one of the grids as an image, choosing the format by the
+/// extension of the path.
+fn export(args: &[String]) -> Result<String, String> {
+    let options = ["--part", "--scale", "--cycles", "--slice", "--pattern"];
+    let args = Args::parse(args, &["--input"], &options)?;
+    let (day, path) = (args.value(0, "day")?, args.value(1, "path")?);
+    let part_b = args.part_b()?;
+    let picture = match day {
+        "jigsaw" => jigsaw::picture(part_b, args.get_str("--pattern"))?,
+        "ferry" => ferry::picture(args.has("--input"), part_b),
+        "conway" => {
+            let cycles = args.get("--cycles")?.unwrap_or(6);
+            let slice = args.get_list("--slice")?;
+            conway::picture(args.has("--input"), part_b, cycles, slice)?
+        }
+        day => return Err(format!("{} can't be exported.", day)),
+    };
+    let scale = args.get("--scale")?.unwrap_or(1);
+    picture
+        .with_scale(scale)
+        .save(path)
+        .map_err(|e| e.to_string())?;
+    Ok(format!("Saved {}\n", path))
+}
+
+/// Debugs the message rules, or uses them to generate messages.
+fn grammar(args: &[String]) -> Result<String, String> {
+    let args = Args::parse(
+        args,
+        &["--input"],
+        &["--rule", "--limit", "--depth", "--part"],
+    )?;
+    messages::explain(
+        args.has("--input"),
+        args.part_b()?,
+        args.value(0, "grammar tool")?,
+        args.value(1, "message").ok(),
+        args.get("--rule")?,
+        args.get("--limit")?.unwrap_or(8),
+        args.get("--depth")?.unwrap_or(10),
+    )
+}
+
+/// Runs Conway cubes with any rule in any number of dimensions.
+fn life(args: &[String]) -> Result<String, String> {
+    let options = [
+        "--rule",
+        "--neighborhood",
+        "--radius",
+        "--dims",
+        "--cycles",
+        "--slice",
+    ];
+    let args = Args::parse(args, &["--input"], &options)?;
+    let rule = args.get_str("--rule").unwrap_or("B3/S23");
+    let mut rule = Rule::parse(rule).ok_or(format!("Invalid rule: {}", rule))?;
+    let radius = args.get("--radius")?.unwrap_or(1);
+    if radius < 1 {
+        return Err("The radius must be at least 1.".to_string());
+    }
+    rule = match args.get_str("--neighborhood") {
+        Some("moore") => rule.with_neighborhood(conway::Neighborhood::Moore, radius),
+        Some("von-neumann") => rule.with_neighborhood(conway::Neighborhood::VonNeumann, radius),
+        Some(n) => return Err(format!("Unknown neighborhood: {}", n)),
+        None if args.get_str("--radius").is_some() => {
+            return Err("The radius needs a neighborhood.".to_string())
+        }
+        None => rule,
+    };
+    let dims = args.get("--dims")?.unwrap_or(3);
+    let cycles = args.get("--cycles")?.unwrap_or(6);
+    conway::life(
+        args.has("--input"),
+        rule,
+        dims,
+        cycles,
+        args.get_list("--slice")?,
+    )
+}
+
+/// Runs a bitmask program and shows what ended up in memory.
+fn memory(args: &[String]) -> Result<String, String> {
+    let options = ["--part", "--width", "--radix", "--address"];
+    let args = Args::parse(args, &["--trace", "--input"], &options)?;
+    masks::explain(
+        args.has("--input"),
+        args.value(0, "path").ok(),
+        args.part_b()?,
+        args.get("--width")?.unwrap_or(masks::WIDTH),
+        args.get_str("--radix"),
+        args.get("--address")?,
+        args.has("--trace"),
+    )
+}
+
+/// Replays the ship's movements in the rain.
+fn route(args: &[String]) -> Result<String, String> {
+    let args = Args::parse(args, &["--input"], &["--part", "--svg"])?;
+    rain::explain(args.has("--input"), args.part_b()?, args.get_str("--svg"))
+}
+
+/// Searches the assembled jigsaws for sea monsters or any other shape.
+fn search(args: &[String]) -> Result<String, String> {
+    let args = Args::parse(args, &[], &["--pattern"])?;
+    jigsaw::search(args.get_str("--pattern"))
+}
+
+/// Runs the ferry seats until they repeat.
+fn seats(args: &[String]) -> Result<String, String> {
+    let options = ["--part", "--neighbors", "--limit", "--seat"];
+    let args = Args::parse(args, &["--input"], &options)?;
+    let limit = args.get("--limit")?.unwrap_or(1000);
+    let (neighbors, seat) = (args.get_str("--neighbors"), args.get_pair("--seat")?);
+    ferry::classify(args.has("--input"), args.part_b()?, neighbors, limit, seat)
+}
+
+/// Counts the trees hit by every slope in a range.
+fn slopes(args: &[String]) -> Result<String, String> {
+    let args = Args::parse(args, &["--input"], &["--max", "--show"])?;
+    let max = args.get_pair("--max")?.unwrap_or((7, 2));
+    trees::survey(args.has("--input"), max, args.get_pair("--show")?)
+}
+
+/// Queries the shuttle timetable.
+fn timetable(args: &[String]) -> Result<String, String> {
+    let options = [
+        "--schedule",
+        "--buses",
+        "--extra",
+        "--from",
+        "--next",
+        "--window",
+    ];
+    let args = Args::parse(args, &["--input"], &options)?;
+    shuttles::explain(
+        args.has("--input"),
+        args.get_str("--schedule"),
+        args.get_str("--buses"),
+        args.get_str("--extra"),
+        args.get("--from")?,
+        args.get("--next")?.unwrap_or(5),
+        args.get("--window")?,
+    )
+}
+
+#[test]
+fn test_args() {
+    let args: Vec<String> = [
+        "ferry", "--part", "b", "--input", "out.svg", "--slice", "1,-2",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let parsed = Args::parse(&args, &["--input"], &["--part", "--slice"]).unwrap();
+    assert_eq!(parsed.value(1, "path"), Ok("out.svg"));
+    assert!(parsed.value(2, "scale").is_err());
+    assert!(parsed.has("--input"));
+    assert_eq!(parsed.part_b(), Ok(true));
+    assert_eq!(parsed.get_list("--slice"), Ok(Some(vec![1, -2])));
+    assert_eq!(parsed.get_pair("--slice"), Ok(Some((1, -2))));
+    assert!(parsed.get_pair::<i32>("--part").is_err());
+    assert!(parsed.get::<usize>("--part").is_err());
+    assert_eq!(parsed.get::<usize>("--scale"), Ok(None));
+    assert!(Args::parse(&args, &[], &["--part", "--slice"]).is_err());
+    assert!(Args::parse(&args[..2], &[], &["--part"]).is_err());
+}